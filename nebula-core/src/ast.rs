@@ -1,8 +1,40 @@
 use crate::sym::Literal;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Program<A> {
+    /// Top-level definitions, in order. Each one can refer to itself and to those defined
+    /// before it.
+    pub defs: Vec<Tag<Def<A>, A>>,
     pub exprs: Vec<Tag<Expr<A>, A>>,
+    /// Fixity declarations, in order. Each one applies to the code that follows it.
+    pub fixities: Vec<Tag<Fixity, A>>,
+}
+
+/// How an operator groups with the operators around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    /// `infixl`: `a - b - c` is `(a - b) - c`.
+    Left,
+    /// `infixr`: `a ^ b ^ c` is `a ^ (b ^ c)`.
+    Right,
+    /// `infix`: `a == b == c` is an error.
+    None,
+}
+
+/// Fixity declaration: `infixl 6 +;`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixity {
+    pub op: String,
+    pub assoc: Assoc,
+    /// From `0`, binding the loosest, to `9`.
+    pub prec: u8,
+}
+
+/// Top-level definition: `name = expr;`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Def<A> {
+    pub name: String,
+    pub value: Tag<Expr<A>, A>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -46,4 +78,19 @@ pub enum Expr<A> {
     Literal(Literal),
     Fun(String, Tag<Box<Tag<Expr<A>, A>>, A>),
     App(Tag<Box<Tag<Expr<A>, A>>, A>, Tag<Box<Tag<Expr<A>, A>>, A>),
+    /// `let x = value in body`, or `body where x = value`. The value is tagged with the
+    /// location of the binding.
+    Let(
+        String,
+        Tag<Box<Tag<Expr<A>, A>>, A>,
+        Tag<Box<Tag<Expr<A>, A>>, A>,
+    ),
+    /// `if cond then a else b`. Each part is tagged with the location of its keyword.
+    If(
+        Tag<Box<Tag<Expr<A>, A>>, A>,
+        Tag<Box<Tag<Expr<A>, A>>, A>,
+        Tag<Box<Tag<Expr<A>, A>>, A>,
+    ),
+    /// Placeholder for code that failed to parse.
+    Error,
 }
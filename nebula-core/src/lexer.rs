@@ -1,22 +1,16 @@
 use crate::sym::{Keyword, Literal, Punctuation, Sym};
-use crate::Loc;
+use crate::{check, Diagnostic, Loc};
 use std::iter::Peekable;
-use std::ops::Neg;
 use std::str::Chars;
 
+type LexResult<T> = Result<T, Diagnostic>;
+
 enum Number {
     Integer(i64),
     Double(f64),
 }
 
 impl Number {
-    fn negate(self) -> Self {
-        match self {
-            Self::Integer(i) => Self::Integer(i.neg()),
-            Self::Double(d) => Self::Double(d.neg()),
-        }
-    }
-
     fn into_sym(self) -> Sym {
         match self {
             Self::Integer(i) => Sym::Literal(Literal::Integer(i)),
@@ -25,6 +19,7 @@ impl Number {
     }
 }
 
+#[derive(Clone)]
 struct Characters<'a> {
     peekable: Peekable<Chars<'a>>,
     pub line: u64,
@@ -72,6 +67,61 @@ impl Token {
     }
 }
 
+/// Chars operators are made of. `.` and `\\` are left out, as they delimit lambdas.
+pub fn is_operator_char(ch: char) -> bool {
+    matches!(
+        ch,
+        '!' | '#'
+            | '$'
+            | '%'
+            | '&'
+            | '*'
+            | '+'
+            | '-'
+            | '/'
+            | '<'
+            | '='
+            | '>'
+            | '?'
+            | '@'
+            | '^'
+            | '|'
+            | '~'
+            | ':'
+    )
+}
+
+/// Tells if the identifier is an operator, like `+` or `>>=`.
+pub fn is_operator(name: &str) -> bool {
+    name.chars().next().is_some_and(is_operator_char)
+}
+
+/// Tells if the token can be the last one of a term, so that an operator may follow it.
+fn ends_term(sym: &Sym) -> bool {
+    match sym {
+        Sym::Id(name) => !is_operator(name),
+        Sym::Literal(_) | Sym::Error => true,
+        Sym::Punctuation(p) => matches!(p, Punctuation::RParen | Punctuation::RBracket),
+        _ => false,
+    }
+}
+
+/// Tells if a raw string starts here: `r` followed by any number of `#` then `"`.
+fn is_raw_string(chars: &Characters) -> bool {
+    let mut ahead = chars.clone();
+    if ahead.next() != Some('r') {
+        return false;
+    }
+
+    loop {
+        match ahead.next() {
+            Some('#') => continue,
+            Some('"') => return true,
+            _ => return false,
+        }
+    }
+}
+
 pub struct Tokenizer<'a> {
     query: &'a str,
 }
@@ -81,8 +131,18 @@ impl<'a> Tokenizer<'a> {
         Self { query }
     }
 
-    /// Tokenize the statement and produce a vector of tokens with location information
+    /// Tokenize the statement and produce a vector of tokens with location information. Fails
+    /// with every lexical error found.
     pub fn tokenize(&self) -> eyre::Result<Vec<Token>> {
+        let (tokens, diagnostics) = self.tokenize_all();
+        check(&diagnostics)?;
+
+        Ok(tokens)
+    }
+
+    /// Tokenizes the whole statement, a lexeme that can't be read becoming a `Sym::Error`
+    /// token along with a diagnostic.
+    pub fn tokenize_all(&self) -> (Vec<Token>, Vec<Diagnostic>) {
         let mut state = Characters {
             peekable: self.query.chars().peekable(),
             line: 1,
@@ -90,9 +150,27 @@ impl<'a> Tokenizer<'a> {
         };
 
         let mut tokens: Vec<Token> = vec![];
+        let mut diagnostics = Vec::new();
         let mut pos = state.loc();
+        let mut after_term = false;
+
+        loop {
+            let start = state.clone();
+
+            let item = match self.next_token_item(&mut state, after_term) {
+                Ok(Some(item)) => item,
+                Ok(None) => break,
+                Err(error) => {
+                    diagnostics.push(error);
+                    self.skip_invalid(&start, &mut state);
+                    Sym::Error
+                }
+            };
+
+            if !item.is_trivia() {
+                after_term = ends_term(&item);
+            }
 
-        while let Some(item) = self.next_token_item(&mut state)? {
             tokens.push(Token { item, loc: pos });
 
             pos = state.loc();
@@ -103,117 +181,133 @@ impl<'a> Tokenizer<'a> {
             loc: pos,
         });
 
-        Ok(tokens)
+        (tokens, diagnostics)
     }
 
-    fn next_token_item(&self, chars: &mut Characters) -> eyre::Result<Option<Sym>> {
-        let prev_loc = chars.loc();
-        match chars.peek() {
-            None => Ok(None),
-            Some(ch) => match ch {
-                _ if ch.is_ascii_whitespace() => {
-                    chars.next();
-                    while let Some(c) = chars.peek() {
-                        if !c.is_whitespace() {
-                            break;
+    /// Skips the rest of a lexeme that failed to be read from `start`. Strings are skipped up
+    /// to their closing quote, and numbers up to the end of their digits and letters. A char
+    /// literal is skipped up to a closing quote found on the same line, and right after its
+    /// first char otherwise, so what follows is read again.
+    fn skip_invalid<'b>(&self, start: &Characters<'b>, chars: &mut Characters<'b>) {
+        let mut ahead = start.clone();
+
+        match ahead.next() {
+            Some('"') => {
+                loop {
+                    match ahead.next() {
+                        None | Some('"') => break,
+                        Some('\\') => {
+                            ahead.next();
                         }
-
-                        chars.next();
+                        Some(_) => {}
                     }
-
-                    Ok(Some(Sym::Whitespace))
                 }
 
-                ':' => {
-                    chars.next();
-                    let loc = chars.loc();
+                *chars = ahead;
+            }
 
-                    if let Some(':') = chars.next() {
-                        return Ok(Some(Sym::Punctuation(Punctuation::DoubleColon)));
+            Some('\'') => {
+                if ahead.peek().is_some_and(|c| *c != '\n' && *c != '\'') {
+                    // The char, or the start of an escape sequence.
+                    if ahead.next() == Some('\\') && ahead.peek() != Some(&'\n') {
+                        ahead.next();
                     }
-
-                    eyre::bail!("{} expected ':'", loc);
                 }
 
-                '.' => self.consume(chars, Sym::Punctuation(Punctuation::Dot)),
-                ',' => self.consume(chars, Sym::Punctuation(Punctuation::Comma)),
-                '\\' => self.consume(chars, Sym::Punctuation(Punctuation::Backslash)),
-                '[' => self.consume(chars, Sym::Punctuation(Punctuation::LBracket)),
-                ']' => self.consume(chars, Sym::Punctuation(Punctuation::RBracket)),
-                '(' => self.consume(chars, Sym::Punctuation(Punctuation::LParen)),
-                ')' => self.consume(chars, Sym::Punctuation(Punctuation::RParen)),
-                '_' => self.consume(chars, Sym::Underscore),
-
-                '<' => {
-                    chars.next();
-                    if let Some('=') = chars.peek() {
-                        return self.consume(chars, Sym::Id("<=".to_string()));
+                let mut rest = ahead.clone();
+                while let Some(ch) = rest.next() {
+                    match ch {
+                        '\'' => {
+                            ahead = rest;
+                            break;
+                        }
+                        '\n' => break,
+                        _ => {}
                     }
-
-                    Ok(Some(Sym::Id("<".to_string())))
                 }
 
-                '>' => {
-                    chars.next();
-                    if let Some('=') = chars.peek() {
-                        return self.consume(chars, Sym::Id(">=".to_string()));
-                    }
+                *chars = ahead;
+            }
 
-                    Ok(Some(Sym::Id(">".to_string())))
+            Some(ch) if ch.is_ascii_digit() || ch == '-' => {
+                while chars
+                    .peek()
+                    .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+                {
+                    chars.next();
                 }
+            }
 
-                '"' => {
-                    let mut string = String::new();
+            // An unexpected symbol.
+            _ if chars.loc() == start.loc() => {
+                chars.next();
+            }
 
-                    chars.next();
+            _ => {}
+        }
+    }
 
-                    while let Some(ch) = chars.peek() {
-                        if *ch == '"' {
-                            return self.consume(chars, Sym::Literal(Literal::String(string)));
-                        }
+    /// Reads the next token. `after_term` tells if the previous significant token ends a term,
+    /// in which case a `-` right before a digit is the subtraction operator, so `x-1` is
+    /// `x - 1`, and a negative number literal otherwise, like in `f (-1)` or `x * -1`.
+    fn next_token_item(&self, chars: &mut Characters, after_term: bool) -> LexResult<Option<Sym>> {
+        let prev_loc = chars.loc();
+        let raw_string = is_raw_string(chars);
+        let second = {
+            let mut ahead = chars.clone();
+            ahead.next();
+            ahead.next()
+        };
 
-                        if *ch == '\n' {
-                            eyre::bail!("{} string literal is malformed", chars.loc());
+        match chars.peek() {
+            None => Ok(None),
+            Some(ch) => match ch {
+                _ if ch.is_ascii_whitespace() => {
+                    chars.next();
+                    while let Some(c) = chars.peek() {
+                        if !c.is_whitespace() {
+                            break;
                         }
 
-                        string.push(*ch);
                         chars.next();
                     }
 
-                    eyre::bail!("{} incomplete string literal", chars.loc());
+                    Ok(Some(Sym::Whitespace))
                 }
 
-                '+' => self.consume(chars, Sym::Id("+".to_string())),
-                '*' => self.consume(chars, Sym::Id("*".to_string())),
+                '.' | '→' => self.consume(chars, Sym::Punctuation(Punctuation::Dot)),
+                ',' => self.consume(chars, Sym::Punctuation(Punctuation::Comma)),
+                ';' => self.consume(chars, Sym::Punctuation(Punctuation::Semicolon)),
+                '\\' | 'λ' => self.consume(chars, Sym::Punctuation(Punctuation::Backslash)),
+                '[' => self.consume(chars, Sym::Punctuation(Punctuation::LBracket)),
+                ']' => self.consume(chars, Sym::Punctuation(Punctuation::RBracket)),
+                '(' => self.consume(chars, Sym::Punctuation(Punctuation::LParen)),
+                ')' => self.consume(chars, Sym::Punctuation(Punctuation::RParen)),
+                '_' => self.consume(chars, Sym::Underscore),
 
-                '-' => {
-                    if let Some(ch) = chars.peek() {
-                        if ch.is_ascii_digit() {
-                            let num = self.parse_number(chars)?.negate();
-                            return Ok(Some(num.into_sym()));
-                        }
-                    }
+                '"' => self.parse_string(chars),
+                'r' if raw_string => self.parse_raw_string(chars),
 
-                    self.consume(chars, Sym::Id("-".to_string()))
-                }
+                '\'' => self.parse_char(chars),
 
-                '=' => {
-                    chars.next();
-                    if let Some('=') = chars.peek() {
-                        return self.consume(chars, Sym::Id("==".to_string()));
-                    }
+                '{' if second == Some('-') => self.parse_block_comment(chars),
 
-                    Ok(Some(Sym::Eq))
+                '-' if !after_term && second.is_some_and(|c| c.is_ascii_digit()) => {
+                    Ok(Some(self.parse_number(chars)?.into_sym()))
                 }
 
-                _ if ch.is_ascii_alphabetic() => {
+                _ if is_operator_char(*ch) => self.parse_operator(chars),
+
+                // Any letter can start an identifier, and trailing primes make `x'` a name of
+                // its own.
+                _ if ch.is_alphabetic() => {
                     let mut ident = String::new();
 
                     ident.push(*ch);
                     chars.next();
 
                     while let Some(ch) = chars.peek() {
-                        if !ch.is_ascii_alphanumeric() && *ch != '_' {
+                        if !ch.is_alphanumeric() && *ch != '_' && *ch != '\'' {
                             break;
                         }
 
@@ -227,6 +321,12 @@ impl<'a> Tokenizer<'a> {
                         "if" => Ok(Some(Sym::Keyword(Keyword::If))),
                         "then" => Ok(Some(Sym::Keyword(Keyword::Then))),
                         "else" => Ok(Some(Sym::Keyword(Keyword::Else))),
+                        "let" => Ok(Some(Sym::Keyword(Keyword::Let))),
+                        "in" => Ok(Some(Sym::Keyword(Keyword::In))),
+                        "where" => Ok(Some(Sym::Keyword(Keyword::Where))),
+                        "infixl" => Ok(Some(Sym::Keyword(Keyword::Infixl))),
+                        "infixr" => Ok(Some(Sym::Keyword(Keyword::Infixr))),
+                        "infix" => Ok(Some(Sym::Keyword(Keyword::Infix))),
                         _ => Ok(Some(Sym::Id(ident))),
                     }
                 }
@@ -236,52 +336,622 @@ impl<'a> Tokenizer<'a> {
                     Ok(Some(num.into_sym()))
                 }
 
-                x => eyre::bail!("{} unexpected symbol '{}'", prev_loc, x),
+                x => Err(Diagnostic::new(
+                    prev_loc,
+                    format!("unexpected symbol '{}'", x),
+                )),
             },
         }
     }
 
-    fn parse_number(&self, chars: &mut Characters) -> eyre::Result<Number> {
+    /// Reads a number, negative if it starts with `-`. Decimal numbers are doubles when they
+    /// have a fraction or an exponent, like `1.5e-3`. Integers can also be written in
+    /// hexadecimal, octal or binary with `0x`, `0o` or `0b`. Digits can be grouped with `_`.
+    fn parse_number(&self, chars: &mut Characters) -> LexResult<Number> {
+        let start = chars.loc();
         let mut num = String::new();
 
-        let ch = chars.next().unwrap();
-        num.push(ch);
+        if let Some('-') = chars.peek() {
+            num.push('-');
+            chars.next();
+        }
+
+        let mut ahead = chars.clone();
+        let radix = match (ahead.next(), ahead.next()) {
+            (Some('0'), Some('x')) => Some((16, "hexadecimal")),
+            (Some('0'), Some('o')) => Some((8, "octal")),
+            (Some('0'), Some('b')) => Some((2, "binary")),
+            _ => None,
+        };
+
+        if let Some((radix, kind)) = radix {
+            chars.next();
+            chars.next();
+            self.read_digits(chars, &mut num, radix, kind)?;
+
+            return i64::from_str_radix(&num, radix)
+                .map(Number::Integer)
+                .map_err(|_| Diagnostic::new(start, format!("{} literal is out of range", kind)));
+        }
 
         let mut is_double = false;
+        self.read_digits(chars, &mut num, 10, "decimal")?;
+
+        let mut ahead = chars.clone();
+        if ahead.next() == Some('.') && ahead.peek().is_some_and(|c| c.is_ascii_digit()) {
+            chars.next();
+            num.push('.');
+            self.read_digits(chars, &mut num, 10, "decimal")?;
+            is_double = true;
+        }
+
+        if let Some('e' | 'E') = chars.peek() {
+            let loc = chars.loc();
+            chars.next();
+            num.push('e');
+
+            if let Some(sign @ ('+' | '-')) = chars.peek().copied() {
+                num.push(sign);
+                chars.next();
+            }
+
+            if !chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(Diagnostic::new(loc, "expected the digits of the exponent"));
+            }
+
+            self.read_digits(chars, &mut num, 10, "decimal")?;
+            is_double = true;
+        }
+
+        if is_double {
+            match num.parse::<f64>() {
+                Ok(d) if d.is_finite() => Ok(Number::Double(d)),
+                _ => Err(Diagnostic::new(
+                    start,
+                    "floating point literal is out of range",
+                )),
+            }
+        } else {
+            num.parse::<i64>()
+                .map(Number::Integer)
+                .map_err(|_| Diagnostic::new(start, "integer literal is out of range"))
+        }
+    }
+
+    /// Reads digits of the given radix into `num`, skipping the `_` that separate them. Letters
+    /// right after hexadecimal, octal or binary digits are reported as invalid digits.
+    fn read_digits(
+        &self,
+        chars: &mut Characters,
+        num: &mut String,
+        radix: u32,
+        kind: &str,
+    ) -> LexResult<()> {
+        let start = chars.loc();
+        let mut separator = None;
+        let mut digits = 0;
+
+        loop {
+            let loc = chars.loc();
+
+            match chars.peek().copied() {
+                Some('_') if separator.is_none() && digits > 0 => {
+                    separator = Some(loc);
+                    chars.next();
+                }
+
+                Some('_') => return Err(Diagnostic::new(loc, "'_' must be placed between digits")),
+
+                Some(ch) if ch.is_digit(radix) => {
+                    num.push(ch);
+                    separator = None;
+                    digits += 1;
+                    chars.next();
+                }
+
+                Some(ch) if radix != 10 && ch.is_ascii_alphanumeric() => {
+                    return Err(Diagnostic::new(
+                        loc,
+                        format!("invalid digit '{}' in {} literal", ch, kind),
+                    ))
+                }
+
+                _ => break,
+            }
+        }
+
+        if digits == 0 {
+            return Err(Diagnostic::new(start, format!("expected {} digits", kind)));
+        }
+
+        if let Some(loc) = separator {
+            return Err(Diagnostic::new(loc, "'_' must be placed between digits"));
+        }
+
+        Ok(())
+    }
+
+    /// Strings may span several lines and hold escape sequences.
+    fn parse_string(&self, chars: &mut Characters) -> LexResult<Option<Sym>> {
+        let start = chars.loc();
+        let mut string = String::new();
+
+        chars.next();
+
         while let Some(ch) = chars.peek() {
-            if !ch.is_ascii_digit() && *ch != '.' {
-                break;
+            match ch {
+                '"' => return self.consume(chars, Sym::Literal(Literal::String(string))),
+                '\\' => string.push(self.parse_escape(chars)?),
+                _ => {
+                    string.push(*ch);
+                    chars.next();
+                }
             }
+        }
 
-            if *ch == '.' {
-                if !is_double {
-                    is_double = true;
-                } else {
-                    eyre::bail!("{} invalid number format", chars.loc());
+        Err(Diagnostic::new(start, "unterminated string literal"))
+    }
+
+    /// `r"..."`, or `r#"..."#` with as many `#` on both sides, holds every char verbatim until
+    /// the closing delimiter.
+    fn parse_raw_string(&self, chars: &mut Characters) -> LexResult<Option<Sym>> {
+        let start = chars.loc();
+        let mut hashes = 0;
+
+        chars.next();
+        while let Some('#') = chars.next() {
+            hashes += 1;
+        }
+
+        let mut string = String::new();
+        while let Some(ch) = chars.next() {
+            if ch == '"' {
+                let mut ahead = chars.clone();
+                if (0..hashes).all(|_| ahead.next() == Some('#')) {
+                    *chars = ahead;
+                    return Ok(Some(Sym::Literal(Literal::String(string))));
                 }
             }
 
-            num.push(*ch);
+            string.push(ch);
+        }
+
+        Err(Diagnostic::new(start, "unterminated raw string literal"))
+    }
+
+    /// Reads a run of operator chars. A run of dashes starts a line comment instead, and `=`
+    /// and `::` are punctuation.
+    fn parse_operator(&self, chars: &mut Characters) -> LexResult<Option<Sym>> {
+        let mut op = String::new();
+
+        while let Some(ch) = chars.peek() {
+            if !is_operator_char(*ch) {
+                break;
+            }
+
+            op.push(*ch);
             chars.next();
         }
 
-        if is_double {
-            let dou = num
-                .parse::<f64>()
-                .map_err(|e| eyre::eyre!("{} invalid float number: {}", chars.loc(), e))?;
+        match op.as_str() {
+            "=" => Ok(Some(Sym::Eq)),
+            "::" => Ok(Some(Sym::Punctuation(Punctuation::DoubleColon))),
+            _ if op.len() > 1 && op.chars().all(|c| c == '-') => self.parse_line_comment(op, chars),
+            _ => Ok(Some(Sym::Id(op))),
+        }
+    }
 
-            Ok(Number::Double(dou))
-        } else {
-            let num = num
-                .parse::<i64>()
-                .map_err(|e| eyre::eyre!("{} invalid integer number: {}", chars.loc(), e))?;
+    /// `-- comment` runs until the end of the line, which isn't part of it. `comment` holds the
+    /// dashes already read.
+    fn parse_line_comment(
+        &self,
+        mut comment: String,
+        chars: &mut Characters,
+    ) -> LexResult<Option<Sym>> {
+        while let Some(ch) = chars.peek() {
+            if *ch == '\n' {
+                break;
+            }
+
+            comment.push(*ch);
+            chars.next();
+        }
+
+        Ok(Some(Sym::Comment(comment)))
+    }
+
+    /// `{- comment -}` may span several lines and hold other block comments.
+    fn parse_block_comment(&self, chars: &mut Characters) -> LexResult<Option<Sym>> {
+        let mut starts = vec![chars.loc()];
+        let mut comment = String::new();
+
+        comment.extend(chars.next());
+        comment.extend(chars.next());
+
+        while let Some(start) = starts.last().copied() {
+            let loc = chars.loc();
+
+            match chars.next() {
+                None => return Err(Diagnostic::new(start, "unterminated block comment")),
+
+                Some('{') if chars.peek() == Some(&'-') => {
+                    starts.push(loc);
+                    comment.push('{');
+                    comment.extend(chars.next());
+                }
+
+                Some('-') if chars.peek() == Some(&'}') => {
+                    starts.pop();
+                    comment.push('-');
+                    comment.extend(chars.next());
+                }
 
-            Ok(Number::Integer(num))
+                Some(ch) => comment.push(ch),
+            }
+        }
+
+        Ok(Some(Sym::Comment(comment)))
+    }
+
+    fn parse_char(&self, chars: &mut Characters) -> LexResult<Option<Sym>> {
+        let start = chars.loc();
+        chars.next();
+
+        let ch = match chars.peek() {
+            None | Some('\n') => return Err(Diagnostic::new(start, "unterminated char literal")),
+            Some('\'') => return Err(Diagnostic::new(start, "empty char literal")),
+            Some('\\') => self.parse_escape(chars)?,
+            Some(ch) => {
+                let ch = *ch;
+                chars.next();
+                ch
+            }
+        };
+
+        if let Some('\'') = chars.peek() {
+            return self.consume(chars, Sym::Literal(Literal::Char(ch)));
+        }
+
+        // Tells a literal holding too many chars from one missing its closing quote.
+        let extra = chars.loc();
+        let mut ahead = chars.clone();
+        while let Some(ch) = ahead.next() {
+            match ch {
+                '\'' => {
+                    return Err(Diagnostic::new(
+                        extra,
+                        "char literal holds more than one char",
+                    ))
+                }
+                '\n' => break,
+                _ => {}
+            }
         }
+
+        Err(Diagnostic::new(start, "unterminated char literal"))
     }
 
-    fn consume(&self, chars: &mut Characters, item: Sym) -> eyre::Result<Option<Sym>> {
+    /// Reads an escape sequence, starting at its backslash.
+    fn parse_escape(&self, chars: &mut Characters) -> LexResult<char> {
+        let loc = chars.loc();
+        chars.next();
+
+        match chars.next() {
+            None | Some('\n') => Err(Diagnostic::new(loc, "incomplete escape sequence")),
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('r') => Ok('\r'),
+            Some('0') => Ok('\0'),
+            Some(ch @ ('\\' | '\'' | '"')) => Ok(ch),
+            Some('u') => self.parse_unicode_escape(loc, chars),
+            Some(ch) => Err(Diagnostic::new(
+                loc,
+                format!("unknown escape sequence '\\{}'", ch),
+            )),
+        }
+    }
+
+    /// Reads the `{XXXX}` part of a `\u{XXXX}` escape sequence starting at `loc`.
+    fn parse_unicode_escape(&self, loc: Loc, chars: &mut Characters) -> LexResult<char> {
+        if chars.next() != Some('{') {
+            return Err(Diagnostic::new(loc, "expected '{' after '\\u'"));
+        }
+
+        let mut hex = String::new();
+        loop {
+            match chars.next() {
+                Some('}') => break,
+                Some(ch) if ch.is_ascii_hexdigit() && hex.len() < 6 => hex.push(ch),
+                _ => return Err(Diagnostic::new(
+                    loc,
+                    "malformed unicode escape, expected 1 to 6 hexadecimal digits between braces",
+                )),
+            }
+        }
+
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| Diagnostic::new(loc, format!("invalid unicode code point '{}'", hex)))
+    }
+
+    fn consume(&self, chars: &mut Characters, item: Sym) -> LexResult<Option<Sym>> {
         chars.next();
         Ok(Some(item))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tokens of the code, without the end of file marker.
+    fn lex(code: &str) -> Vec<Sym> {
+        let mut tokens: Vec<Sym> = Tokenizer::new(code)
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(|token| token.item)
+            .collect();
+
+        assert_eq!(tokens.pop(), Some(Sym::EOF));
+        tokens
+    }
+
+    /// The error of code holding a single invalid lexeme.
+    fn error(code: &str) -> String {
+        let (_, diagnostics) = Tokenizer::new(code).tokenize_all();
+
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        diagnostics[0].to_string()
+    }
+
+    fn char(c: char) -> Sym {
+        Sym::Literal(Literal::Char(c))
+    }
+
+    #[test]
+    fn chars() {
+        assert_eq!(lex("'a'"), [char('a')]);
+        assert_eq!(lex("'λ'"), [char('λ')]);
+        assert_eq!(lex("'\"'"), [char('"')]);
+    }
+
+    #[test]
+    fn char_escapes() {
+        assert_eq!(lex(r"'\n'"), [char('\n')]);
+        assert_eq!(lex(r"'\t'"), [char('\t')]);
+        assert_eq!(lex(r"'\r'"), [char('\r')]);
+        assert_eq!(lex(r"'\0'"), [char('\0')]);
+        assert_eq!(lex(r"'\\'"), [char('\\')]);
+        assert_eq!(lex(r"'\''"), [char('\'')]);
+        assert_eq!(lex(r"'\u{1F600}'"), [char('😀')]);
+    }
+
+    #[test]
+    fn invalid_chars() {
+        assert_eq!(error("''"), "1:1 empty char literal");
+        assert_eq!(error("'a"), "1:1 unterminated char literal");
+        assert_eq!(error("'ab'"), "1:3 char literal holds more than one char");
+        assert_eq!(error(r"'\q'"), r"1:2 unknown escape sequence '\q'");
+        assert_eq!(
+            error(r"'\u{D800}'"),
+            "1:2 invalid unicode code point 'D800'"
+        );
+        assert_eq!(
+            error(r"'\u{1234567}'"),
+            "1:2 malformed unicode escape, expected 1 to 6 hexadecimal digits between braces"
+        );
+    }
+
+    fn string(s: &str) -> Sym {
+        Sym::Literal(Literal::String(s.to_string()))
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(lex(r#""""#), [string("")]);
+        assert_eq!(lex(r#""a 'b' c""#), [string("a 'b' c")]);
+        assert_eq!(lex(r#""a\n\"b\"\t\u{e9}""#), [string("a\n\"b\"\té")]);
+        assert_eq!(lex("\"two\nlines\""), [string("two\nlines")]);
+    }
+
+    #[test]
+    fn raw_strings() {
+        assert_eq!(lex(r#"r"C:\path\n""#), [string(r"C:\path\n")]);
+        assert_eq!(lex(r###"r##"a "# b"##"###), [string(r##"a "# b"##)]);
+        assert_eq!(lex("r"), [Sym::Id("r".to_string())]);
+    }
+
+    #[test]
+    fn invalid_strings() {
+        assert_eq!(error("\"abc"), "1:1 unterminated string literal");
+        assert_eq!(error(r##"r#"abc""##), "1:1 unterminated raw string literal");
+        assert_eq!(error(r#""a\qb""#), r"1:3 unknown escape sequence '\q'");
+    }
+
+    fn comment(s: &str) -> Sym {
+        Sym::Comment(s.to_string())
+    }
+
+    fn id(s: &str) -> Sym {
+        Sym::Id(s.to_string())
+    }
+
+    #[test]
+    fn line_comments() {
+        assert_eq!(
+            lex("x -- comment\ny"),
+            [
+                id("x"),
+                Sym::Whitespace,
+                comment("-- comment"),
+                Sym::Whitespace,
+                id("y")
+            ]
+        );
+        assert_eq!(lex("--- more dashes"), [comment("--- more dashes")]);
+        assert_eq!(lex("-->"), [id("-->")]);
+    }
+
+    #[test]
+    fn block_comments() {
+        assert_eq!(
+            lex("x{- a {- nested -}\n b -}y"),
+            [id("x"), comment("{- a {- nested -}\n b -}"), id("y")]
+        );
+        assert_eq!(error("{- {- -}"), "1:1 unterminated block comment");
+    }
+
+    #[test]
+    fn unicode_lambdas() {
+        let lambda = [
+            Sym::Punctuation(Punctuation::Backslash),
+            id("x"),
+            Sym::Punctuation(Punctuation::Dot),
+            Sym::Whitespace,
+            id("x"),
+        ];
+
+        assert_eq!(lex("\\x. x"), lambda);
+        assert_eq!(lex("λx→ x"), lambda);
+    }
+
+    #[test]
+    fn unicode_identifiers() {
+        assert_eq!(
+            lex("café x' ñ_1"),
+            [
+                id("café"),
+                Sym::Whitespace,
+                id("x'"),
+                Sym::Whitespace,
+                id("ñ_1")
+            ]
+        );
+    }
+
+    fn int(i: i64) -> Sym {
+        Sym::Literal(Literal::Integer(i))
+    }
+
+    fn double(d: f64) -> Sym {
+        Sym::Literal(Literal::Double(d))
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(lex("42"), [int(42)]);
+        assert_eq!(lex("1_000_000"), [int(1_000_000)]);
+        assert_eq!(
+            lex("0xFF 0o17 0b101"),
+            [int(255), Sym::Whitespace, int(15), Sym::Whitespace, int(5)]
+        );
+        assert_eq!(lex("1.5"), [double(1.5)]);
+        assert_eq!(
+            lex("1e3 2.5E-1"),
+            [double(1e3), Sym::Whitespace, double(0.25)]
+        );
+    }
+
+    #[test]
+    fn negative_numbers() {
+        assert_eq!(lex("-9223372036854775808"), [int(i64::MIN)]);
+        assert_eq!(lex("-0x10"), [int(-16)]);
+        assert_eq!(
+            lex("x * -1"),
+            [id("x"), Sym::Whitespace, id("*"), Sym::Whitespace, int(-1)]
+        );
+        assert_eq!(
+            lex("(-1)"),
+            [
+                Sym::Punctuation(Punctuation::LParen),
+                int(-1),
+                Sym::Punctuation(Punctuation::RParen)
+            ]
+        );
+    }
+
+    #[test]
+    fn minus_after_a_term_is_an_operator() {
+        assert_eq!(lex("x-1"), [id("x"), id("-"), int(1)]);
+        assert_eq!(lex("10-1"), [int(10), id("-"), int(1)]);
+        assert_eq!(
+            lex("x - 1"),
+            [id("x"), Sym::Whitespace, id("-"), Sym::Whitespace, int(1)]
+        );
+        assert_eq!(lex("f -1"), [id("f"), Sym::Whitespace, id("-"), int(1)]);
+        assert_eq!(
+            lex("(x) -1"),
+            [
+                Sym::Punctuation(Punctuation::LParen),
+                id("x"),
+                Sym::Punctuation(Punctuation::RParen),
+                Sym::Whitespace,
+                id("-"),
+                int(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        assert_eq!(
+            error("9223372036854775808"),
+            "1:1 integer literal is out of range"
+        );
+        assert_eq!(
+            error("0x1g"),
+            "1:4 invalid digit 'g' in hexadecimal literal"
+        );
+        assert_eq!(error("0b"), "1:3 expected binary digits");
+        assert_eq!(error("1__0"), "1:3 '_' must be placed between digits");
+        assert_eq!(error("1_"), "1:2 '_' must be placed between digits");
+        assert_eq!(error("1e"), "1:2 expected the digits of the exponent");
+        assert_eq!(error("1e999"), "1:1 floating point literal is out of range");
+    }
+
+    /// Every error of the code, along with its tokens where the invalid lexemes are
+    /// `Sym::Error`.
+    fn recover(code: &str) -> (Vec<Sym>, Vec<String>) {
+        let (tokens, diagnostics) = Tokenizer::new(code).tokenize_all();
+        let tokens = tokens
+            .into_iter()
+            .map(|token| token.item)
+            .filter(|sym| !sym.is_trivia() && *sym != Sym::EOF)
+            .collect();
+
+        (
+            tokens,
+            diagnostics.iter().map(ToString::to_string).collect(),
+        )
+    }
+
+    #[test]
+    fn reports_every_invalid_lexeme() {
+        let (tokens, errors) = recover("'ab' 'c 0x1g 1__0 {");
+
+        assert_eq!(tokens, vec![Sym::Error; 5]);
+        assert_eq!(
+            errors,
+            [
+                "1:3 char literal holds more than one char",
+                "1:6 unterminated char literal",
+                "1:12 invalid digit 'g' in hexadecimal literal",
+                "1:16 '_' must be placed between digits",
+                "1:19 unexpected symbol '{'",
+            ]
+        );
+    }
+
+    #[test]
+    fn resumes_after_invalid_lexemes() {
+        assert_eq!(recover("'' x").0, [Sym::Error, id("x")]);
+        assert_eq!(recover(r"'\q' x").0, [Sym::Error, id("x")]);
+        assert_eq!(recover(r"'\u{D800}' x").0, [Sym::Error, id("x")]);
+        assert_eq!(recover("'a\nx").0, [Sym::Error, id("x")]);
+        assert_eq!(recover(r#""a\qb" x"#).0, [Sym::Error, id("x")]);
+        assert_eq!(recover("0b12 x").0, [Sym::Error, id("x")]);
+        assert_eq!(recover("1 ` x").0, [int(1), Sym::Error, id("x")]);
+    }
+}
@@ -1,4 +1,4 @@
-use ast::Program;
+use ast::{Fixity, Program};
 use lexer::Tokenizer;
 use parser::Parser;
 
@@ -6,6 +6,7 @@ pub mod ast;
 pub mod lexer;
 pub mod parser;
 pub mod register;
+pub mod resolve;
 pub mod scope;
 pub mod sym;
 
@@ -21,10 +22,63 @@ impl std::fmt::Display for Loc {
     }
 }
 
+/// An error found in the code, at the given location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub loc: Loc,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(loc: Loc, message: impl Into<String>) -> Self {
+        Self {
+            loc,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.loc, self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Fails with every diagnostic, one per line, if there are any.
+pub(crate) fn check(diagnostics: &[Diagnostic]) -> eyre::Result<()> {
+    if !diagnostics.is_empty() {
+        let errors: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+        eyre::bail!("{}", errors.join("\n"));
+    }
+
+    Ok(())
+}
+
 pub fn parse(code: impl AsRef<str>) -> eyre::Result<Program<Loc>> {
+    parse_with(code, &[])
+}
+
+/// Parses the code knowing the fixity of operators declared elsewhere. Fails with every
+/// lexical and syntax error found.
+pub fn parse_with(code: impl AsRef<str>, fixities: &[Fixity]) -> eyre::Result<Program<Loc>> {
+    let (prog, diagnostics) = parse_all(code, fixities);
+    check(&diagnostics)?;
+
+    Ok(prog)
+}
+
+/// Parses the code as far as possible, returning a program where the code that failed to parse
+/// is replaced by `Expr::Error`, along with every lexical and syntax error found, in order.
+pub fn parse_all(code: impl AsRef<str>, fixities: &[Fixity]) -> (Program<Loc>, Vec<Diagnostic>) {
     let tokenizer = Tokenizer::new(code.as_ref());
-    let tokens = tokenizer.tokenize()?;
-    let parser = Parser::new(tokens.as_ref());
+    let (tokens, mut diagnostics) = tokenizer.tokenize_all();
+    let parser = Parser::new(tokens.as_ref()).with_fixities(fixities);
+    let (prog, errors) = parser.parse_all();
+
+    diagnostics.extend(errors);
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.loc.line, diagnostic.loc.col));
 
-    parser.parse()
+    (prog, diagnostics)
 }
@@ -1,22 +1,64 @@
-use crate::ast::{Expr, Program, Tag};
-use crate::lexer::Token;
+use crate::ast::{Assoc, Def, Expr, Fixity, Program, Tag};
+use crate::lexer::{is_operator, Token};
 use crate::sym::{Keyword, Literal, Punctuation, Sym};
-use crate::Loc;
-use std::iter::Peekable;
+use crate::{check, Diagnostic, Loc};
+use std::collections::HashMap;
+use std::iter::{Filter, Peekable};
+use std::rc::Rc;
 use std::slice::Iter;
 
+/// A `name = expr` binding of a `let` or a `where`, with the location of the name.
+type Binding = (String, Loc, Tag<Expr<Loc>, Loc>);
+
+/// A sub-expression, tagged with the location of the construct it belongs to.
+type SubExpr = Tag<Box<Tag<Expr<Loc>, Loc>>, Loc>;
+
+type ParseResult<T> = Result<T, Diagnostic>;
+
+/// Tokens seen by the parser, whitespace and comments being left out.
+type Tokens<'a> = Filter<Iter<'a, Token>, fn(&&'a Token) -> bool>;
+
 #[derive(Clone)]
 pub struct ParserState<'a> {
-    peekable: Peekable<Iter<'a, Token>>,
+    peekable: Peekable<Tokens<'a>>,
+    /// Shared between the copies made to look ahead, only copied when a declaration changes
+    /// it.
+    fixities: Rc<HashMap<String, Fixity>>,
+    /// Errors the parser recovered from.
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl<'a> ParserState<'a> {
     pub fn new(tokens: &'a [Token]) -> Self {
+        let fixities = default_fixities()
+            .into_iter()
+            .map(|fixity| (fixity.op.clone(), fixity))
+            .collect();
+
         Self {
-            peekable: tokens.iter().peekable(),
+            peekable: tokens
+                .iter()
+                .filter(is_significant as fn(&&'a Token) -> bool)
+                .peekable(),
+            fixities: Rc::new(fixities),
+            diagnostics: Vec::new(),
         }
     }
 
+    /// Declares the fixity of an operator for the rest of the parsing.
+    pub fn declare(&mut self, fixity: Fixity) {
+        Rc::make_mut(&mut self.fixities).insert(fixity.op.clone(), fixity);
+    }
+
+    /// Fixity of the operator. Undeclared operators are `infixl 9`.
+    pub fn fixity(&self, op: &str) -> Fixity {
+        self.fixities.get(op).cloned().unwrap_or(Fixity {
+            op: op.to_string(),
+            assoc: Assoc::Left,
+            prec: 9,
+        })
+    }
+
     pub fn look_ahead(&mut self) -> &'a Token {
         self.peekable.peek().unwrap()
     }
@@ -25,39 +67,25 @@ impl<'a> ParserState<'a> {
         self.peekable.next().unwrap()
     }
 
-    pub fn skip_spaces(&mut self) {
-        loop {
-            match self.look_ahead().item() {
-                Sym::Whitespace => {
-                    self.shift();
-                }
-
-                _ => break,
-            }
-        }
-    }
-
     pub fn next_keyword(&mut self, key: Keyword) -> bool {
         self.next_sym(Sym::Keyword(key))
     }
 
-    fn expect(&mut self, expected: Sym) -> eyre::Result<()> {
+    fn expect(&mut self, expected: Sym) -> ParseResult<()> {
         let loc = self.loc();
         let token = self.shift();
 
         if &expected != token.item() {
-            eyre::bail!(
-                "{} expected {} but got {} instead",
+            return Err(Diagnostic::new(
                 loc,
-                expected,
-                token.item(),
-            );
+                format!("expected {} but got {} instead", expected, token.item()),
+            ));
         }
 
         Ok(())
     }
 
-    fn expect_ident(&mut self) -> eyre::Result<String> {
+    fn expect_ident(&mut self) -> ParseResult<String> {
         let loc = self.loc();
         let token = self.shift();
 
@@ -65,18 +93,17 @@ impl<'a> ParserState<'a> {
             return Ok(n.clone());
         }
 
-        eyre::bail!(
-            "{} expected an identifier but got {} instead",
+        Err(Diagnostic::new(
             loc,
-            token.item(),
-        );
+            format!("expected an identifier but got {} instead", token.item()),
+        ))
     }
 
-    // fn expect_keyword(&mut self, expected: Keyword) -> eyre::Result<()> {
-    //     self.expect(Sym::Keyword(expected))
-    // }
+    fn expect_keyword(&mut self, expected: Keyword) -> ParseResult<()> {
+        self.expect(Sym::Keyword(expected))
+    }
 
-    fn expect_punctuation(&mut self, expected: Punctuation) -> eyre::Result<()> {
+    fn expect_punctuation(&mut self, expected: Punctuation) -> ParseResult<()> {
         self.expect(Sym::Punctuation(expected))
     }
 
@@ -99,7 +126,132 @@ impl<'a> ParserState<'a> {
         self.next_sym(Sym::Punctuation(expected))
     }
 
-    pub fn parse_expr(&mut self) -> eyre::Result<Expr<Loc>> {
+    /// Parses an expression, optionally followed by `where` bindings.
+    pub fn parse_expr(&mut self) -> ParseResult<Expr<Loc>> {
+        let loc = self.loc();
+        let expr = self.parse_infix(0, None)?.item;
+
+        if !self.followed_by_keyword(Keyword::Where) {
+            return Ok(expr);
+        }
+
+        self.shift();
+        let bindings = self.parse_bindings()?;
+
+        Ok(nest_bindings(
+            bindings,
+            Tag {
+                item: expr,
+                tag: loc,
+            },
+        ))
+    }
+
+    /// Parses applications separated by infix operators, by precedence climbing. Only operators
+    /// whose precedence is at least `min` are consumed. `previous` is the operator the expression
+    /// is the right operand of, if any, as its associativity constrains the operators that follow.
+    fn parse_infix(
+        &mut self,
+        min: u8,
+        mut previous: Option<Fixity>,
+    ) -> ParseResult<Tag<Expr<Loc>, Loc>> {
+        let start = self.loc();
+        let mut lhs = self.parse_app()?;
+
+        loop {
+            let loc = self.loc();
+
+            let fixity = match self.look_ahead().item() {
+                Sym::Id(op) if is_operator(op) => self.fixity(op),
+                _ => break,
+            };
+
+            if fixity.prec < min {
+                break;
+            }
+
+            if let Some(previous) = previous.as_ref().filter(|p| p.prec == fixity.prec) {
+                if previous.assoc != fixity.assoc || fixity.assoc == Assoc::None {
+                    return Err(Diagnostic::new(
+                        loc,
+                        format!(
+                            "'{}' can't follow '{}' without parentheses, they have the same precedence",
+                            fixity.op, previous.op
+                        ),
+                    ));
+                }
+            }
+
+            self.shift();
+
+            let rhs = match fixity.assoc {
+                Assoc::Right => self.parse_infix(fixity.prec, Some(fixity.clone()))?,
+                Assoc::Left | Assoc::None => self.parse_infix(fixity.prec + 1, None)?,
+            };
+
+            let op = Tag {
+                item: Expr::Literal(Literal::Ref(fixity.op.clone())),
+                tag: loc,
+            };
+
+            lhs = Tag {
+                item: apply(
+                    Tag {
+                        item: apply(op, lhs),
+                        tag: start,
+                    },
+                    rhs,
+                ),
+                tag: start,
+            };
+
+            previous = Some(fixity);
+        }
+
+        Ok(lhs)
+    }
+
+    /// Parses terms applied by juxtaposition, `f a b` being `((f a) b)`. A lambda, a `let` or an
+    /// `if` extends as far right as possible, so it can only be the last argument.
+    fn parse_app(&mut self) -> ParseResult<Tag<Expr<Loc>, Loc>> {
+        let start = self.loc();
+        let mut app = Tag {
+            item: self.parse_term()?,
+            tag: start,
+        };
+
+        loop {
+            if !self.followed_by_term() {
+                return Ok(app);
+            }
+
+            let loc = self.loc();
+            let arg = Tag {
+                item: self.parse_term()?,
+                tag: loc,
+            };
+
+            app = Tag {
+                item: apply(app, arg),
+                tag: start,
+            };
+        }
+    }
+
+    /// Tells if a term that can be an argument starts here. Operators can only be applied to
+    /// arguments, like in `(+ 1 2)`.
+    fn followed_by_term(&mut self) -> bool {
+        self.followed_by(|sym| match sym {
+            Sym::Id(name) => !is_operator(name),
+            Sym::Literal(_)
+            | Sym::Error
+            | Sym::Punctuation(Punctuation::LParen | Punctuation::Backslash)
+            | Sym::Keyword(Keyword::Let | Keyword::If) => true,
+            _ => false,
+        })
+    }
+
+    fn parse_term(&mut self) -> ParseResult<Expr<Loc>> {
         let token = self.look_ahead();
 
         match token.item() {
@@ -113,64 +265,285 @@ impl<'a> ParserState<'a> {
                 Ok(Expr::Literal(l.clone()))
             }
 
-            // Function
+            // Already reported by the lexer.
+            Sym::Error => {
+                self.shift();
+                Ok(Expr::Error)
+            }
+
+            // Function, `\x y. body` being sugar for `\x. \y. body`
             Sym::Punctuation(Punctuation::Backslash) => {
                 let start = self.loc();
                 self.shift();
-                self.skip_spaces();
+
                 let name = self.expect_ident()?;
-                self.skip_spaces();
-                self.expect_punctuation(Punctuation::Dot)?;
-                self.skip_spaces();
+
+                let mut binders = Vec::new();
+                while !self.next_punct(Punctuation::Dot) {
+                    let loc = self.loc();
+                    binders.push((self.expect_ident()?, loc));
+                }
+
+                self.shift();
                 let body_loc = self.loc();
-                let body = self.parse_expr()?;
+                let body = Tag {
+                    item: self.parse_expr()?,
+                    tag: body_loc,
+                };
+
+                // Each inner lambda is located at its binder.
+                let body = binders
+                    .into_iter()
+                    .rev()
+                    .fold(body, |body, (name, loc)| Tag {
+                        item: Expr::Fun(
+                            name,
+                            Tag {
+                                item: Box::new(body),
+                                tag: loc,
+                            },
+                        ),
+                        tag: loc,
+                    });
 
                 Ok(Expr::Fun(
                     name,
                     Tag {
-                        item: Box::new(Tag {
-                            item: body,
-                            tag: body_loc,
-                        }),
+                        item: Box::new(body),
                         tag: start,
                     },
                 ))
             }
 
-            // Application
-            Sym::Punctuation(Punctuation::LParen) => {
-                let start = self.loc();
+            Sym::Keyword(Keyword::Let) => {
                 self.shift();
-                self.skip_spaces();
-                let fun_loc = self.loc();
-                let fun = self.parse_expr()?;
-                self.expect(Sym::Whitespace)?;
-                let arg_loc = self.loc();
-                let arg = self.parse_expr()?;
-                self.skip_spaces();
-                self.expect_punctuation(Punctuation::RParen)?;
-                let end = self.loc();
-
-                Ok(Expr::App(
-                    Tag {
-                        item: Box::new(Tag {
-                            item: fun,
-                            tag: fun_loc,
-                        }),
+                let bindings = self.parse_bindings()?;
+                self.expect_keyword(Keyword::In)?;
+                let body_loc = self.loc();
+                let body = self.parse_expr()?;
 
-                        tag: start,
-                    },
+                Ok(nest_bindings(
+                    bindings,
                     Tag {
-                        item: Box::new(Tag {
-                            item: arg,
-                            tag: arg_loc,
-                        }),
-                        tag: end,
+                        item: body,
+                        tag: body_loc,
                     },
                 ))
             }
 
-            _ => eyre::bail!("{} unexpected token {}", self.loc(), token.item()),
+            Sym::Keyword(Keyword::If) => {
+                let cond = self.parse_branch(Keyword::If)?;
+                let then = self.parse_branch(Keyword::Then)?;
+                let otherwise = self.parse_branch(Keyword::Else)?;
+
+                Ok(Expr::If(cond, then, otherwise))
+            }
+
+            // Grouping, an error inside being skipped up to the closing parenthesis
+            Sym::Punctuation(Punctuation::LParen) => {
+                self.shift();
+                let group = self.peekable.clone();
+
+                let result = self.parse_expr().and_then(|expr| {
+                    self.expect_punctuation(Punctuation::RParen)?;
+                    Ok(expr)
+                });
+
+                match result {
+                    Ok(expr) => Ok(expr),
+                    Err(error) => {
+                        self.peekable = group;
+                        if !self.skip_group() {
+                            return Err(error);
+                        }
+
+                        self.diagnostics.push(error);
+                        Ok(Expr::Error)
+                    }
+                }
+            }
+
+            _ => Err(Diagnostic::new(
+                self.loc(),
+                format!("unexpected token {}", token.item()),
+            )),
+        }
+    }
+
+    /// Parses a keyword followed by an expression, as found in a conditional.
+    fn parse_branch(&mut self, keyword: Keyword) -> ParseResult<SubExpr> {
+        let start = self.loc();
+        self.expect_keyword(keyword)?;
+        let loc = self.loc();
+        let expr = self.parse_expr()?;
+
+        Ok(Tag {
+            item: Box::new(Tag {
+                item: expr,
+                tag: loc,
+            }),
+            tag: start,
+        })
+    }
+
+    /// Parses comma-separated `name = expr` bindings.
+    fn parse_bindings(&mut self) -> ParseResult<Vec<Binding>> {
+        let mut bindings = Vec::new();
+
+        loop {
+            let loc = self.loc();
+            let name = self.expect_ident()?;
+            self.expect(Sym::Eq)?;
+            let value_loc = self.loc();
+            let value = self.parse_expr()?;
+            bindings.push((
+                name,
+                loc,
+                Tag {
+                    item: value,
+                    tag: value_loc,
+                },
+            ));
+
+            if !self.next_punct(Punctuation::Comma) {
+                return Ok(bindings);
+            }
+
+            self.shift();
+        }
+    }
+
+    /// Parses `infixl 6 +, -;`, `infixr` or `infix` and declares those operators.
+    pub fn parse_fixity(&mut self) -> ParseResult<Vec<Tag<Fixity, Loc>>> {
+        let assoc = match self.shift().item() {
+            Sym::Keyword(Keyword::Infixl) => Assoc::Left,
+            Sym::Keyword(Keyword::Infixr) => Assoc::Right,
+            _ => Assoc::None,
+        };
+
+        let loc = self.loc();
+        let prec = match self.shift().item() {
+            Sym::Literal(Literal::Integer(prec @ 0..=9)) => *prec as u8,
+            sym => {
+                return Err(Diagnostic::new(
+                    loc,
+                    format!("expected a precedence from 0 to 9 but got {} instead", sym),
+                ))
+            }
+        };
+
+        let mut fixities = Vec::new();
+        loop {
+            let loc = self.loc();
+            let op = self.expect_ident()?;
+
+            if !is_operator(&op) {
+                return Err(Diagnostic::new(
+                    loc,
+                    format!("expected an operator but got {} instead", op),
+                ));
+            }
+
+            let fixity = Fixity { op, assoc, prec };
+            self.declare(fixity.clone());
+            fixities.push(Tag {
+                item: fixity,
+                tag: loc,
+            });
+
+            if !self.next_punct(Punctuation::Comma) {
+                break;
+            }
+
+            self.shift();
+        }
+
+        self.expect_punctuation(Punctuation::Semicolon)?;
+
+        Ok(fixities)
+    }
+
+    /// Tells if a fixity declaration starts here.
+    pub fn followed_by_fixity(&mut self) -> bool {
+        self.followed_by(|sym| {
+            matches!(
+                sym,
+                Sym::Keyword(Keyword::Infixl | Keyword::Infixr | Keyword::Infix)
+            )
+        })
+    }
+
+    /// Tells if a definition starts here: an identifier followed by `=`.
+    pub fn followed_by_def(&mut self) -> bool {
+        let mut ahead = self.clone();
+
+        if !ahead.followed_by(|sym| matches!(sym, Sym::Id(_))) {
+            return false;
+        }
+
+        ahead.shift();
+
+        ahead.next_sym(Sym::Eq)
+    }
+
+    /// Parses `name = expr;`. An error in the value is recovered from, the value being
+    /// `Expr::Error` then.
+    pub fn parse_def(&mut self) -> ParseResult<Def<Loc>> {
+        let start = self.peekable.clone();
+        let name = self.expect_ident()?;
+        self.expect(Sym::Eq)?;
+        let loc = self.loc();
+
+        let result = self.parse_expr().and_then(|value| {
+            self.expect_punctuation(Punctuation::Semicolon)?;
+            Ok(value)
+        });
+
+        let value = result.unwrap_or_else(|error| {
+            self.recover(start, error);
+            Expr::Error
+        });
+
+        Ok(Def {
+            name,
+            value: Tag {
+                item: value,
+                tag: loc,
+            },
+        })
+    }
+
+    /// Skips the tokens of a group up to its closing parenthesis. Tells if it was found, a
+    /// `;` or the end of file being reached first otherwise.
+    fn skip_group(&mut self) -> bool {
+        let mut depth = 0;
+
+        loop {
+            match self.look_ahead().item() {
+                Sym::Punctuation(Punctuation::Semicolon) | Sym::EOF => return false,
+                Sym::Punctuation(Punctuation::LParen) => depth += 1,
+                Sym::Punctuation(Punctuation::RParen) if depth == 0 => {
+                    self.shift();
+                    return true;
+                }
+                Sym::Punctuation(Punctuation::RParen) => depth -= 1,
+                _ => {}
+            }
+
+            self.shift();
+        }
+    }
+
+    /// Records the error and resumes after the `;` ending the top-level item that started at
+    /// `start`.
+    fn recover(&mut self, start: Peekable<Tokens<'a>>, error: Diagnostic) {
+        self.diagnostics.push(error);
+        self.peekable = start;
+
+        while !self.next_sym(Sym::EOF) {
+            if self.shift().item() == &Sym::Punctuation(Punctuation::Semicolon) {
+                return;
+            }
         }
     }
 
@@ -179,29 +552,469 @@ impl<'a> ParserState<'a> {
     }
 }
 
+fn is_significant(token: &&Token) -> bool {
+    !token.item().is_trivia()
+}
+
+/// Fixities of the operators bound to primitives.
+fn default_fixities() -> Vec<Fixity> {
+    let fixity = |op: &str, assoc, prec| Fixity {
+        op: op.to_string(),
+        assoc,
+        prec,
+    };
+
+    vec![
+        fixity("*", Assoc::Left, 7),
+        fixity("+", Assoc::Left, 6),
+        fixity("-", Assoc::Left, 6),
+        fixity("<", Assoc::None, 4),
+        fixity("<=", Assoc::None, 4),
+        fixity(">", Assoc::None, 4),
+        fixity(">=", Assoc::None, 4),
+        fixity("==", Assoc::None, 4),
+    ]
+}
+
+fn apply(fun: Tag<Expr<Loc>, Loc>, arg: Tag<Expr<Loc>, Loc>) -> Expr<Loc> {
+    Expr::App(
+        Tag {
+            tag: fun.tag,
+            item: Box::new(fun),
+        },
+        Tag {
+            tag: arg.tag,
+            item: Box::new(arg),
+        },
+    )
+}
+
+/// Nests bindings around the body, each one being visible to the ones that follow.
+fn nest_bindings(bindings: Vec<Binding>, body: Tag<Expr<Loc>, Loc>) -> Expr<Loc> {
+    let body = bindings
+        .into_iter()
+        .rev()
+        .fold(body, |body, (name, loc, value)| Tag {
+            item: Expr::Let(
+                name,
+                Tag {
+                    item: Box::new(value),
+                    tag: loc,
+                },
+                Tag {
+                    tag: body.tag,
+                    item: Box::new(body),
+                },
+            ),
+            tag: loc,
+        });
+
+    body.item
+}
+
 pub struct Parser<'a> {
     tokens: &'a [Token],
+    fixities: Vec<Fixity>,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(tokens: &'a [Token]) -> Self {
-        Self { tokens }
+        Self {
+            tokens,
+            fixities: Vec::new(),
+        }
     }
 
+    /// Fixities declared beforehand, by code parsed earlier for instance.
+    pub fn with_fixities(mut self, fixities: &[Fixity]) -> Self {
+        self.fixities.extend_from_slice(fixities);
+        self
+    }
+
+    /// Parses definitions and fixity declarations followed by an optional main expression.
+    /// Fails with every syntax error found.
     pub fn parse(&self) -> eyre::Result<Program<Loc>> {
+        let (prog, diagnostics) = self.parse_all();
+        check(&diagnostics)?;
+
+        Ok(prog)
+    }
+
+    /// Parses the whole code, recovering from syntax errors at closing parentheses and at the
+    /// end of top-level items. The code that failed to parse is replaced by `Expr::Error`.
+    pub fn parse_all(&self) -> (Program<Loc>, Vec<Diagnostic>) {
         let mut state = ParserState::new(self.tokens);
 
-        let mut exprs = Vec::new();
-        let loc = state.loc();
+        for fixity in self.fixities.iter() {
+            state.declare(fixity.clone());
+        }
 
-        exprs.push(Tag {
-            item: state.parse_expr()?,
-            tag: loc,
-        });
+        let mut defs = Vec::new();
+        let mut exprs: Vec<Tag<Expr<Loc>, Loc>> = Vec::new();
+        let mut fixities = Vec::new();
+
+        while !state.next_sym(Sym::EOF) {
+            let start = state.peekable.clone();
+            let loc = state.loc();
+
+            // Nothing can follow the main expression, one that failed to parse aside.
+            if exprs.iter().any(|expr| !matches!(expr.item, Expr::Error)) {
+                let error = format!(
+                    "expected {} but got {} instead",
+                    Sym::EOF,
+                    state.look_ahead().item()
+                );
+                state.recover(start, Diagnostic::new(loc, error));
+                continue;
+            }
+
+            if state.followed_by_fixity() {
+                match state.parse_fixity() {
+                    Ok(declared) => fixities.extend(declared),
+                    Err(error) => state.recover(start, error),
+                }
+            } else if state.followed_by_def() {
+                match state.parse_def() {
+                    Ok(def) => defs.push(Tag {
+                        item: def,
+                        tag: loc,
+                    }),
+                    Err(error) => state.recover(start, error),
+                }
+            } else {
+                let result = state.parse_expr().and_then(|expr| {
+                    if state.next_punct(Punctuation::Semicolon) {
+                        state.shift();
+                    } else if !state.next_sym(Sym::EOF) {
+                        let loc = state.loc();
+                        return Err(Diagnostic::new(
+                            loc,
+                            format!(
+                                "expected ';' or {} but got {} instead",
+                                Sym::EOF,
+                                state.look_ahead().item()
+                            ),
+                        ));
+                    }
+
+                    Ok(expr)
+                });
+
+                let expr = match result {
+                    Ok(expr) => expr,
+                    Err(error) => {
+                        state.recover(start, error);
+                        Expr::Error
+                    }
+                };
+
+                exprs.push(Tag {
+                    item: expr,
+                    tag: loc,
+                });
+            }
+        }
 
-        state.skip_spaces();
-        state.expect(Sym::EOF)?;
+        let prog = Program {
+            defs,
+            exprs,
+            fixities,
+        };
+
+        (prog, state.diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Prints the expression with every application between parentheses, like `(f a b)`.
+    fn show(expr: &Expr<Loc>) -> String {
+        match expr {
+            Expr::Literal(lit) => lit.to_string(),
+            Expr::Fun(name, body) => format!("(\\{}. {})", name, show(&body.item.item)),
+            Expr::App(..) => {
+                let mut args = Vec::new();
+                let mut fun = expr;
+                while let Expr::App(lhs, rhs) = fun {
+                    args.push(show(&rhs.item.item));
+                    fun = &lhs.item.item;
+                }
+
+                args.push(show(fun));
+                args.reverse();
+                format!("({})", args.join(" "))
+            }
+            Expr::Let(name, value, body) => format!(
+                "let {} = {} in {}",
+                name,
+                show(&value.item.item),
+                show(&body.item.item)
+            ),
+            Expr::If(cond, then, otherwise) => format!(
+                "if {} then {} else {}",
+                show(&cond.item.item),
+                show(&then.item.item),
+                show(&otherwise.item.item)
+            ),
+            Expr::Error => "<error>".to_string(),
+        }
+    }
+
+    /// The main expression of the code.
+    fn parse(code: &str) -> String {
+        show(&crate::parse(code).unwrap().exprs[0].item)
+    }
+
+    /// The main expression of the code, where what failed to parse is `<error>`.
+    fn recover(code: &str) -> String {
+        let (prog, _) = crate::parse_all(code, &[]);
+        show(&prog.exprs[0].item)
+    }
+
+    /// Every error of the code.
+    fn errors(code: &str) -> Vec<String> {
+        let (_, diagnostics) = crate::parse_all(code, &[]);
+        diagnostics.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn primitive_operators_precedence() {
+        assert_eq!(parse("1 + 2 * 3"), "(+ 1 (* 2 3))");
+        assert_eq!(parse("1 * 2 - 3"), "(- (* 1 2) 3)");
+        assert_eq!(parse("1 - 2 - 3"), "(- (- 1 2) 3)");
+        assert_eq!(parse("1 + 2 < 3 * 4"), "(< (+ 1 2) (* 3 4))");
+        assert_eq!(parse("(1 + 2) * 3"), "(* (+ 1 2) 3)");
+    }
 
-        Ok(Program { exprs })
+    #[test]
+    fn application_binds_tighter_than_operators() {
+        assert_eq!(parse("f x + g y"), "(+ (f x) (g y))");
+        assert_eq!(parse("(+ 1 2)"), "(+ 1 2)");
+    }
+
+    #[test]
+    fn declared_fixities() {
+        assert_eq!(parse("infixr 5 ++; a ++ b ++ c"), "(++ a (++ b c))");
+        assert_eq!(parse("infixl 1 |>; x |> f |> g"), "(|> (|> x f) g)");
+        assert_eq!(parse("infixl 8 +; 1 + 2 * 3"), "(* (+ 1 2) 3)");
+    }
+
+    #[test]
+    fn undeclared_operators_are_infixl_9() {
+        assert_eq!(parse("a <> b + c"), "(+ (<> a b) c)");
+        assert_eq!(parse("a <> b <> c"), "(<> (<> a b) c)");
+    }
+
+    #[test]
+    fn fixities_declared_beforehand() {
+        let fixity = Fixity {
+            op: "++".to_string(),
+            assoc: Assoc::Right,
+            prec: 5,
+        };
+        let prog = crate::parse_with("a ++ b ++ c", &[fixity]).unwrap();
+
+        assert_eq!(show(&prog.exprs[0].item), "(++ a (++ b c))");
+    }
+
+    #[test]
+    fn operators_of_same_precedence_must_associate() {
+        assert_eq!(
+            errors("1 == 2 == 3"),
+            ["1:8 '==' can't follow '==' without parentheses, they have the same precedence"]
+        );
+        assert_eq!(
+            errors("infixr 6 +++; 1 + 2 +++ 3"),
+            ["1:21 '+++' can't follow '+' without parentheses, they have the same precedence"]
+        );
+    }
+
+    #[test]
+    fn mixed_associativity_fails_in_either_order() {
+        assert_eq!(
+            errors("infixr 5 ++; infixl 5 --+; a ++ b --+ c"),
+            ["1:35 '--+' can't follow '++' without parentheses, they have the same precedence"]
+        );
+        assert_eq!(
+            errors("infixr 5 ++; infixl 5 --+; a --+ b ++ c"),
+            ["1:36 '++' can't follow '--+' without parentheses, they have the same precedence"]
+        );
+    }
+
+    #[test]
+    fn invalid_fixity_declarations() {
+        assert_eq!(
+            errors("infixl 10 +; 1"),
+            ["1:8 expected a precedence from 0 to 9 but got 10 instead"]
+        );
+        assert_eq!(
+            errors("infixl 6 f; 1"),
+            ["1:10 expected an operator but got f instead"]
+        );
+    }
+
+    #[test]
+    fn application_by_juxtaposition() {
+        assert_eq!(parse("f a b"), "(f a b)");
+        assert_eq!(parse("f (g a) b"), "(f (g a) b)");
+        assert_eq!(parse("(((f a)) b)"), "(f a b)");
+        assert_eq!(parse("(x)"), "x");
+    }
+
+    #[test]
+    fn whitespace_and_comments_do_not_matter() {
+        assert_eq!(parse("((f)(x))"), "(f x)");
+        assert_eq!(parse("f\nx"), "(f x)");
+        assert_eq!(parse("f\tx"), "(f x)");
+        assert_eq!(parse("f {- arg -} x"), "(f x)");
+        assert_eq!(parse("f -- arg\n  x"), "(f x)");
+        assert_eq!(parse("(f x )"), "(f x)");
+        assert_eq!(parse("( \\x. x )"), "(\\x. x)");
+    }
+
+    #[test]
+    fn lambda_bodies_extend_to_the_right() {
+        assert_eq!(parse("\\x. x 5"), "(\\x. (x 5))");
+        assert_eq!(parse("\\x y. x + y"), "(\\x. (\\y. (+ x y)))");
+        assert_eq!(parse("f \\x. x 5"), "(f (\\x. (x 5)))");
+        assert_eq!(parse("(f \\x. x 5)"), "(f (\\x. (x 5)))");
+    }
+
+    #[test]
+    fn lambdas_of_several_binders_nest() {
+        let prog = crate::parse("\\x y z. z y x").unwrap();
+        assert_eq!(show(&prog.exprs[0].item), "(\\x. (\\y. (\\z. (z y x))))");
+
+        // The body of each lambda is located at the lambda, which is itself located at its binder.
+        let loc = |line, col| Loc { line, col };
+        let mut locs = Vec::new();
+        let mut expr = &prog.exprs[0].item;
+        while let Expr::Fun(name, body) = expr {
+            locs.push((name.as_str(), body.tag, body.item.tag));
+            expr = &body.item.item;
+        }
+
+        assert_eq!(
+            locs,
+            [
+                ("x", loc(1, 1), loc(1, 4)),
+                ("y", loc(1, 4), loc(1, 6)),
+                ("z", loc(1, 6), loc(1, 9)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parentheses_only_group() {
+        assert_eq!(parse("(\\x. x 5)"), "(\\x. (x 5))");
+        assert_eq!(
+            parse("(\\x. x x) (\\x. x x)"),
+            "((\\x. (x x)) (\\x. (x x)))"
+        );
+        assert_eq!(parse("(\\x. \\y. y x) a b"), "((\\x. (\\y. (y x))) a b)");
+        assert_eq!(parse("(\\x. x + 1) 5"), "((\\x. (+ x 1)) 5)");
+    }
+
+    #[test]
+    fn fully_parenthesised_style() {
+        assert_eq!(parse("((\\x. x) 5)"), "((\\x. x) 5)");
+        assert_eq!(parse("(\\x. (x x))"), "(\\x. (x x))");
+        assert_eq!(
+            parse("((\\x. (x x)) (\\x. (x x)))"),
+            "((\\x. (x x)) (\\x. (x x)))"
+        );
+    }
+
+    #[test]
+    fn one_error_after_the_main_expression() {
+        assert_eq!(
+            errors("1 )"),
+            ["1:3 expected ';' or 'end of file' but got ) instead"]
+        );
+        assert_eq!(
+            errors("f x = x; 1"),
+            ["1:5 expected ';' or 'end of file' but got '=' instead"]
+        );
+        assert_eq!(
+            errors("1; a = 2;"),
+            ["1:4 expected 'end of file' but got a instead"]
+        );
+    }
+
+    #[test]
+    fn recovers_at_the_end_of_items() {
+        assert_eq!(
+            errors("a = 1 +; b = ); c = 3; c"),
+            ["1:8 unexpected token ;", "1:14 unexpected token )"]
+        );
+
+        let (prog, _) = crate::parse_all("a = 1 +; b = ); c = 3; c", &[]);
+        let defs: Vec<String> = prog
+            .defs
+            .iter()
+            .map(|def| format!("{} = {}", def.item.name, show(&def.item.value.item)))
+            .collect();
+
+        assert_eq!(defs, ["a = <error>", "b = <error>", "c = 3"]);
+        assert_eq!(show(&prog.exprs[0].item), "c");
+        assert_eq!(recover("1 + ; 2"), "<error>");
+    }
+
+    #[test]
+    fn recovers_at_closing_parentheses() {
+        assert_eq!(
+            errors("f (1 +) (g =) x"),
+            [
+                "1:7 unexpected token )",
+                "1:12 expected ) but got '=' instead"
+            ]
+        );
+        assert_eq!(recover("f (1 +) (g =) x"), "(f <error> <error> x)");
+    }
+
+    #[test]
+    fn let_bindings_nest_in_order() {
+        assert_eq!(
+            parse("let x = 1, y = x + 1 in y * 2"),
+            "let x = 1 in let y = (+ x 1) in (* y 2)"
+        );
+        assert_eq!(parse("let x = x in x"), "let x = x in x");
+    }
+
+    #[test]
+    fn where_bindings_wrap_the_expression() {
+        assert_eq!(
+            parse("y * 2 where x = 1, y = x + 1"),
+            "let x = 1 in let y = (+ x 1) in (* y 2)"
+        );
+        assert_eq!(parse("\\x. x where y = 1"), "(\\x. let y = 1 in x)");
+    }
+
+    #[test]
+    fn bindings_are_located_at_their_name() {
+        let prog = crate::parse("let x = 1,\n    y = 2 in y").unwrap();
+        let Expr::Let(_, value, body) = &prog.exprs[0].item else {
+            panic!("expected a let");
+        };
+        let Expr::Let(_, inner, _) = &body.item.item else {
+            panic!("expected a nested let");
+        };
+
+        assert_eq!(value.tag, Loc { line: 1, col: 5 });
+        assert_eq!(inner.tag, Loc { line: 2, col: 5 });
+    }
+
+    #[test]
+    fn invalid_bindings() {
+        assert_eq!(
+            errors("let x = 1; x"),
+            ["1:10 expected in but got ; instead"]
+        );
+        assert_eq!(
+            errors("let 1 = x in x"),
+            ["1:5 expected an identifier but got 1 instead"]
+        );
     }
 }
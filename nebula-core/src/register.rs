@@ -1,13 +1,27 @@
 use std::collections::HashMap;
 
-use crate::scope::Scope;
+use crate::scope::{Scope, ScopeId};
 
 pub struct Entry<A> {
-    _scope: Scope,
-    _name: String,
+    scope: Scope,
+    name: String,
     value: A,
 }
 
+impl<A> Entry<A> {
+    pub fn scope(&self) -> &Scope {
+        &self.scope
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &A {
+        &self.value
+    }
+}
+
 pub struct Register<A> {
     inner: HashMap<String, Entry<A>>,
 }
@@ -22,14 +36,14 @@ impl<A> Default for Register<A> {
 
 impl<A> Register<A> {
     pub fn register(&mut self, scope: &Scope, name: &str, value: A) -> bool {
-        let id = generate_id(scope, name);
+        let id = generate_id(scope.id(), name);
 
         self.inner
             .insert(
                 id,
                 Entry {
-                    _scope: scope.clone(),
-                    _name: name.to_string(),
+                    scope: scope.clone(),
+                    name: name.to_string(),
                     value,
                 },
             )
@@ -37,17 +51,27 @@ impl<A> Register<A> {
     }
 
     pub fn lookup(&self, scope: &Scope, name: &str) -> Option<&A> {
-        let id = generate_id(scope, name);
+        let id = generate_id(scope.id(), name);
 
         self.inner.get(&id).map(|e| &e.value)
     }
 
+    /// Looks the name up in the scope first, then in each of its ancestors, the closest one
+    /// first.
+    pub fn resolve(&self, scope: &Scope, name: &str) -> Option<&Entry<A>> {
+        scope
+            .ancestors()
+            .iter()
+            .rev()
+            .find_map(|id| self.inner.get(&generate_id(*id, name)))
+    }
+
     pub fn remove(&mut self, scope: &Scope, name: &str) {
-        let id = generate_id(scope, name);
+        let id = generate_id(scope.id(), name);
         self.inner.remove(&id);
     }
 }
 
-fn generate_id(scope: &Scope, name: &str) -> String {
-    format!("{}:{}", scope.id(), name)
+fn generate_id(scope: ScopeId, name: &str) -> String {
+    format!("{}:{}", scope, name)
 }
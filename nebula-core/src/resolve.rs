@@ -0,0 +1,296 @@
+use std::fmt::Display;
+
+use crate::{
+    ast::{Expr, Program, Tag},
+    register::Register,
+    scope::{Scope, ScopeId, ScopeTree},
+    sym::Literal,
+    Loc,
+};
+
+/// A variable reference along with the lambda that binds it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved {
+    pub name: String,
+    pub loc: Loc,
+    /// Location of the binding lambda or definition.
+    pub binder: Loc,
+    /// Scope introduced by the binding lambda, the root scope for a definition.
+    pub scope: ScopeId,
+    /// Scope the reference appears in.
+    pub at: ScopeId,
+}
+
+/// A variable reference that no lambda or earlier definition binds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unbound {
+    pub name: String,
+    pub loc: Loc,
+    /// Scope the reference appears in.
+    pub at: ScopeId,
+}
+
+impl Display for Unbound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} unbound variable '{}'", self.loc, self.name)
+    }
+}
+
+/// A lambda that binds a name already bound by an enclosing lambda or a definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shadowing {
+    pub name: String,
+    pub loc: Loc,
+    /// Location of the lambda whose binding is shadowed.
+    pub shadowed: Loc,
+}
+
+impl Display for Shadowing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} variable '{}' shadows the one bound at {}",
+            self.loc, self.name, self.shadowed
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct Resolution {
+    pub scopes: ScopeTree,
+    pub resolved: Vec<Resolved>,
+    pub unbound: Vec<Unbound>,
+    /// References to primitives, which are not errors but would be unbound otherwise.
+    pub primitives: Vec<Unbound>,
+    /// Not an error, the innermost binding wins. Meant to be reported as a warning.
+    pub shadowing: Vec<Shadowing>,
+}
+
+/// Resolves every variable reference of the program to its binding site. Without primitives, any
+/// reference left unbound is an error.
+pub fn resolve(prog: &Program<Loc>) -> Resolution {
+    resolve_with(prog, |_| false)
+}
+
+/// Like [`resolve`], but the references `is_primitive` accepts are primitives instead of
+/// unbound. A name only refers to a primitive when no lambda or definition binds it.
+pub fn resolve_with(prog: &Program<Loc>, is_primitive: impl Fn(&str) -> bool) -> Resolution {
+    let mut reg = Register::default();
+    let mut res = Resolution::default();
+    let root = res.scopes.root();
+
+    // A definition can refer to itself.
+    for def in prog.defs.iter() {
+        res.scopes.bind(&root, &def.item.name);
+        reg.register(&root, &def.item.name, def.tag);
+        resolve_expr(&mut reg, &mut res, &root, &def.item.value);
+    }
+
+    for expr in prog.exprs.iter() {
+        resolve_expr(&mut reg, &mut res, &root, expr);
+    }
+
+    (res.primitives, res.unbound) = res
+        .unbound
+        .into_iter()
+        .partition(|unbound| is_primitive(&unbound.name));
+
+    res
+}
+
+fn resolve_expr(
+    reg: &mut Register<Loc>,
+    res: &mut Resolution,
+    scope: &Scope,
+    expr: &Tag<Expr<Loc>, Loc>,
+) {
+    match &expr.item {
+        Expr::Literal(Literal::Ref(name)) => match reg.resolve(scope, name) {
+            Some(entry) => res.resolved.push(Resolved {
+                name: name.clone(),
+                loc: expr.tag,
+                binder: *entry.value(),
+                scope: entry.scope().id(),
+                at: scope.id(),
+            }),
+
+            None => res.unbound.push(Unbound {
+                name: name.clone(),
+                loc: expr.tag,
+                at: scope.id(),
+            }),
+        },
+
+        Expr::Literal(_) | Expr::Error => {}
+
+        Expr::Fun(name, body) => resolve_binder(reg, res, scope, name, expr.tag, &body.item),
+
+        // The value is outside the scope of its own binding.
+        Expr::Let(name, value, body) => {
+            resolve_expr(reg, res, scope, &value.item);
+            resolve_binder(reg, res, scope, name, value.tag, &body.item);
+        }
+
+        Expr::App(lhs, rhs) => {
+            resolve_expr(reg, res, scope, &lhs.item);
+            resolve_expr(reg, res, scope, &rhs.item);
+        }
+
+        Expr::If(cond, then, otherwise) => {
+            resolve_expr(reg, res, scope, &cond.item);
+            resolve_expr(reg, res, scope, &then.item);
+            resolve_expr(reg, res, scope, &otherwise.item);
+        }
+    }
+}
+
+/// Binds `name` in a new scope, child of `scope`, and resolves `body` within it.
+fn resolve_binder(
+    reg: &mut Register<Loc>,
+    res: &mut Resolution,
+    scope: &Scope,
+    name: &str,
+    loc: Loc,
+    body: &Tag<Expr<Loc>, Loc>,
+) {
+    if let Some(entry) = reg.resolve(scope, name) {
+        res.shadowing.push(Shadowing {
+            name: name.to_string(),
+            loc,
+            shadowed: *entry.value(),
+        });
+    }
+
+    let scope = res.scopes.inherits(scope);
+
+    res.scopes.bind(&scope, name);
+    reg.register(&scope, name, loc);
+    resolve_expr(reg, res, &scope, body);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(refs: &[Unbound]) -> Vec<&str> {
+        refs.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    fn loc(line: u64, col: u64) -> Loc {
+        Loc { line, col }
+    }
+
+    #[test]
+    fn resolves_through_ancestors() {
+        let prog = crate::parse("\\x. \\y. \\z. x").unwrap();
+        let res = resolve(&prog);
+        let x = &res.resolved[0];
+
+        assert_eq!(x.name, "x");
+        assert_eq!(x.loc, loc(1, 13));
+        assert_eq!(x.binder, loc(1, 1));
+        assert_ne!(x.scope, x.at);
+
+        let mut ancestor = Some(x.at);
+        while ancestor.is_some_and(|id| id != x.scope) {
+            ancestor = res.scopes.parent(ancestor.unwrap());
+        }
+        assert_eq!(ancestor, Some(x.scope));
+    }
+
+    #[test]
+    fn each_binder_of_a_lambda_is_located() {
+        let prog = crate::parse("\\x y z. z y x").unwrap();
+        let res = resolve(&prog);
+        let binders: Vec<(&str, Loc)> = res
+            .resolved
+            .iter()
+            .map(|r| (r.name.as_str(), r.binder))
+            .collect();
+
+        assert_eq!(
+            binders,
+            [("z", loc(1, 6)), ("y", loc(1, 4)), ("x", loc(1, 1))]
+        );
+    }
+
+    #[test]
+    fn definitions_are_bound_in_the_root_scope() {
+        let prog = crate::parse("a = 1;\nb = \\x. a x;").unwrap();
+        let res = resolve(&prog);
+        let a = res.resolved.iter().find(|r| r.name == "a").unwrap();
+
+        assert_eq!(a.binder, loc(1, 1));
+        assert_eq!(a.scope, res.scopes.root().id());
+    }
+
+    #[test]
+    fn reports_unbound_variables() {
+        let prog = crate::parse("\\x. x y").unwrap();
+        let res = resolve(&prog);
+
+        assert_eq!(names(&res.unbound), ["y"]);
+        assert_eq!(res.unbound[0].to_string(), "1:7 unbound variable 'y'");
+    }
+
+    fn is_primitive(name: &str) -> bool {
+        ["+", "length"].contains(&name)
+    }
+
+    #[test]
+    fn primitives_are_not_unbound() {
+        let prog = crate::parse("(\\y. y + length \"a\") x").unwrap();
+        let res = resolve_with(&prog, is_primitive);
+
+        assert_eq!(names(&res.unbound), ["x"]);
+        assert_eq!(names(&res.primitives), ["+", "length"]);
+    }
+
+    #[test]
+    fn definitions_hide_primitives() {
+        let prog = crate::parse("length = 1; length").unwrap();
+        let res = resolve_with(&prog, is_primitive);
+
+        assert!(res.unbound.is_empty());
+        assert!(res.primitives.is_empty());
+        assert_eq!(res.resolved.len(), 1);
+    }
+
+    #[test]
+    fn definitions_refer_to_themselves_and_earlier_ones() {
+        let prog = crate::parse("f = \\n. f n; g = f h; g").unwrap();
+        let res = resolve(&prog);
+
+        assert_eq!(names(&res.unbound), ["h"]);
+        assert_eq!(res.resolved.len(), 4);
+    }
+
+    #[test]
+    fn innermost_binding_wins() {
+        let prog = crate::parse("\\x. \\x. x").unwrap();
+        let res = resolve(&prog);
+
+        assert_eq!(res.resolved[0].binder, loc(1, 5));
+        assert_eq!(res.shadowing.len(), 1);
+        assert_eq!(
+            res.shadowing[0].to_string(),
+            "1:5 variable 'x' shadows the one bound at 1:1"
+        );
+    }
+
+    #[test]
+    fn lambdas_can_shadow_definitions() {
+        let prog = crate::parse("x = 1; \\x. x").unwrap();
+        let res = resolve(&prog);
+
+        assert_eq!(res.resolved[0].binder, loc(1, 8));
+        assert_eq!(res.shadowing[0].shadowed, loc(1, 1));
+    }
+
+    #[test]
+    fn sibling_lambdas_do_not_shadow() {
+        let prog = crate::parse("(\\x. x) (\\x. x)").unwrap();
+
+        assert!(resolve(&prog).shadowing.is_empty());
+    }
+}
@@ -5,6 +5,12 @@ pub struct Scope {
     ancestors: Vec<u32>,
 }
 
+impl Default for Scope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Scope {
     pub fn new() -> Self {
         Self { ancestors: vec![0] }
@@ -19,13 +25,139 @@ impl Scope {
     pub fn id(&self) -> ScopeId {
         self.ancestors.last().copied().unwrap()
     }
+}
+
+/// Allocates scopes with unique ids and remembers how they are nested.
+pub struct ScopeTree {
+    /// Parent of each scope, indexed by scope id. Only the root scope has none.
+    parents: Vec<Option<ScopeId>>,
+    /// Names bound by each scope, indexed by scope id.
+    bindings: Vec<Vec<String>>,
+}
+
+impl Default for ScopeTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScopeTree {
+    pub fn new() -> Self {
+        Self {
+            parents: vec![None],
+            bindings: vec![Vec::new()],
+        }
+    }
+
+    pub fn root(&self) -> Scope {
+        Scope::new()
+    }
+
+    /// Allocates a new scope nested in `parent`.
+    pub fn inherits(&mut self, parent: &Scope) -> Scope {
+        let id = self.parents.len() as ScopeId;
+        let mut ancestors = parent.ancestors.clone();
+
+        self.parents.push(Some(parent.id()));
+        self.bindings.push(Vec::new());
+        ancestors.push(id);
+
+        Scope { ancestors }
+    }
+
+    pub fn parent(&self, scope: ScopeId) -> Option<ScopeId> {
+        self.parents.get(scope as usize).copied().flatten()
+    }
 
-    pub fn inherits(&self) -> Scope {
-        let mut temp = self.ancestors.clone();
-        let parent = temp.last().copied().unwrap();
+    pub fn bind(&mut self, scope: &Scope, name: &str) {
+        self.bindings[scope.id() as usize].push(name.to_string());
+    }
+
+    /// Names bound directly by the scope.
+    pub fn bindings(&self, scope: ScopeId) -> &[String] {
+        self.bindings
+            .get(scope as usize)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Bindings visible from the scope along with the scope that introduces them, the closest
+    /// first. Shadowed bindings are left out.
+    pub fn visible(&self, scope: ScopeId) -> Vec<(ScopeId, &str)> {
+        let mut visible: Vec<(ScopeId, &str)> = Vec::new();
+        let mut current = Some(scope);
+
+        while let Some(id) = current {
+            for name in self.bindings(id).iter().rev() {
+                if visible.iter().all(|(_, n)| n != name) {
+                    visible.push((id, name));
+                }
+            }
+
+            current = self.parent(id);
+        }
+
+        visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_are_nested_in_their_parent() {
+        let mut tree = ScopeTree::new();
+        let root = tree.root();
+        let a = tree.inherits(&root);
+        let b = tree.inherits(&a);
+
+        assert_eq!(tree.parent(root.id()), None);
+        assert_eq!(tree.parent(a.id()), Some(root.id()));
+        assert_eq!(tree.parent(b.id()), Some(a.id()));
+        assert_eq!(b.ancestors(), [root.id(), a.id(), b.id()]);
+    }
+
+    #[test]
+    fn siblings_get_different_ids() {
+        let mut tree = ScopeTree::new();
+        let root = tree.root();
+        let a = tree.inherits(&root);
+        let b = tree.inherits(&root);
+
+        assert_ne!(a.id(), b.id());
+        assert_eq!(tree.parent(a.id()), tree.parent(b.id()));
+    }
+
+    #[test]
+    fn visible_bindings() {
+        let mut tree = ScopeTree::new();
+        let root = tree.root();
+        tree.bind(&root, "f");
+        tree.bind(&root, "x");
+
+        let outer = tree.inherits(&root);
+        tree.bind(&outer, "x");
+        let inner = tree.inherits(&outer);
+        tree.bind(&inner, "y");
+
+        assert_eq!(tree.bindings(outer.id()), ["x"]);
+        assert_eq!(
+            tree.visible(inner.id()),
+            [(inner.id(), "y"), (outer.id(), "x"), (root.id(), "f")]
+        );
+        assert_eq!(tree.visible(root.id()), [(0, "x"), (0, "f")]);
+    }
 
-        temp.push(parent + 1);
+    #[test]
+    fn lambdas_on_both_sides_of_an_application_get_different_scopes() {
+        let prog = crate::parse("(\\x. x) (\\x. x)").unwrap();
+        let res = crate::resolve::resolve(&prog);
+        let (lhs, rhs) = (&res.resolved[0], &res.resolved[1]);
 
-        Self { ancestors: temp }
+        assert_ne!(lhs.scope, rhs.scope);
+        assert_eq!(res.scopes.parent(lhs.scope), res.scopes.parent(rhs.scope));
+        assert_eq!(res.scopes.bindings(lhs.scope), ["x"]);
+        assert_eq!(res.scopes.bindings(rhs.scope), ["x"]);
     }
 }
@@ -6,7 +6,11 @@ pub enum Keyword {
     Then,
     Else,
     Let,
+    In,
     Where,
+    Infixl,
+    Infixr,
+    Infix,
 }
 
 impl Keyword {
@@ -16,7 +20,11 @@ impl Keyword {
             Keyword::Then => "then",
             Keyword::Else => "else",
             Keyword::Let => "let",
+            Keyword::In => "in",
             Keyword::Where => "where",
+            Keyword::Infixl => "infixl",
+            Keyword::Infixr => "infixr",
+            Keyword::Infix => "infix",
         }
     }
 }
@@ -66,6 +74,8 @@ pub enum Punctuation {
     /// Right bracket `]`
     RBracket,
     Comma,
+    /// ';'
+    Semicolon,
     Backslash,
 }
 
@@ -79,6 +89,7 @@ impl Display for Punctuation {
             Punctuation::LBracket => "[",
             Punctuation::RBracket => "]",
             Punctuation::Comma => ",",
+            Punctuation::Semicolon => ";",
             Punctuation::Backslash => "\\",
         };
 
@@ -99,9 +110,20 @@ pub enum Sym {
     Keyword(Keyword),
     /// Whitespace (space, tab, etc)
     Whitespace,
+    /// A `-- line` or `{- block -}` comment, with its delimiters
+    Comment(String),
     /// Equality operator `=`
     Eq,
     Underscore,
+    /// Code that couldn't be read, reported by a diagnostic.
+    Error,
+}
+
+impl Sym {
+    /// Tells if the token carries no meaning for the parser.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Sym::Whitespace | Sym::Comment(_))
+    }
 }
 
 impl Display for Sym {
@@ -113,8 +135,10 @@ impl Display for Sym {
             Sym::Id(i) => i.fmt(f),
             Sym::Keyword(k) => k.fmt(f),
             Sym::Whitespace => write!(f, "<whitespace>"),
+            Sym::Comment(_) => write!(f, "<comment>"),
             Sym::Eq => write!(f, "'='"),
             Sym::Underscore => write!(f, "'_'"),
+            Sym::Error => write!(f, "<invalid token>"),
         }
     }
 }
@@ -1,8 +1,96 @@
+use std::{collections::HashSet, time::Duration};
+
+use clap::Parser;
 use glyph::Options;
-use nebula_core::parse;
-use nebula_runtime::derivation;
+use nebula_core::{
+    ast::{Def, Program, Tag},
+    parse_with,
+    resolve::resolve,
+    Loc,
+};
+use nebula_runtime::{
+    check,
+    config::Config,
+    derivation,
+    prim::Overflow,
+    step::Outcome,
+    strategy::{Form, Strategy},
+};
+
+#[derive(Parser)]
+#[command(about = "Interactive lambda calculus interpreter")]
+struct Args {
+    /// Evaluation strategy: normal, applicative, name, value or need
+    #[arg(long, short, default_value_t = Strategy::NormalOrder)]
+    strategy: Strategy,
+
+    /// Form to stop at: nf, hnf or whnf. Defaults to the strategy's own
+    #[arg(long, short)]
+    form: Option<Form>,
+
+    /// Also contract eta redexes
+    #[arg(long)]
+    eta: bool,
+
+    /// Print every reduction step instead of the final term only
+    #[arg(long, short)]
+    trace: bool,
+
+    /// Print lambdas with λ instead of \
+    #[arg(long, short)]
+    unicode: bool,
+
+    /// Maximum number of reduction steps
+    #[arg(long, default_value_t = 10_000)]
+    max_steps: usize,
+
+    /// Maximum number of nodes a term can hold
+    #[arg(long)]
+    max_size: Option<usize>,
+
+    /// Maximum evaluation time, in milliseconds
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Don't stop when a term reduces back to an earlier one
+    #[arg(long)]
+    no_cycles: bool,
+
+    /// What integer overflow does: checked, wrapping, saturating or promote
+    #[arg(long, default_value_t = Overflow::Checked)]
+    overflow: Overflow,
+
+    /// Encode booleans and conditionals as lambdas
+    #[arg(long)]
+    pure: bool,
+
+    /// Warn about variables shadowing an enclosing binding
+    #[arg(long)]
+    lint: bool,
+}
 
 fn main() -> eyre::Result<()> {
+    let args = Args::parse();
+    let mut config = Config::default()
+        .strategy(args.strategy)
+        .eta(args.eta)
+        .max_steps(args.max_steps)
+        .detect_cycles(!args.no_cycles)
+        .pure(args.pure)
+        .overflow(args.overflow);
+
+    if let Some(form) = args.form {
+        config = config.form(form);
+    }
+
+    if let Some(size) = args.max_size {
+        config = config.max_size(size);
+    }
+
+    if let Some(timeout) = args.timeout {
+        config = config.timeout(Duration::from_millis(timeout));
+    }
+
     let options = Options::default()
         .prompt("π>")
         .header(include_str!("header.txt"))
@@ -11,19 +99,153 @@ fn main() -> eyre::Result<()> {
 
     let mut inputs = glyph::in_memory_inputs(options)?;
 
+    // Definitions and fixities entered so far, available to every later input.
+    let mut defs = Vec::new();
+    let mut fixities = Vec::new();
+
     while let Some(input) = inputs.next_input()? {
         match input {
             glyph::Input::Exit => break,
             glyph::Input::Command(_) => continue,
-            glyph::Input::String(code) => match parse(code) {
+            glyph::Input::String(code) => match parse_with(code, &fixities) {
                 Err(e) => println!("{}", e),
-                Ok(prg) => match derivation(prg) {
-                    Err(e) => println!("{}", e),
-                    Ok(v) => println!("{}", v),
-                },
+                Ok(prg) => {
+                    fixities.extend(prg.fixities.iter().map(|f| f.item.clone()));
+                    evaluate(&args, &config, &mut defs, prg);
+                }
             },
         }
     }
 
     Ok(())
 }
+
+fn evaluate(
+    args: &Args,
+    config: &Config,
+    defs: &mut Vec<Tag<Def<Loc>, Loc>>,
+    mut prg: Program<Loc>,
+) {
+    // A new definition replaces the previous one with the same name, and may refer to
+    // definitions entered after it.
+    let mut all_defs = defs.clone();
+    for def in prg.defs {
+        all_defs.retain(|d| d.item.name != def.item.name);
+        all_defs.push(def);
+    }
+
+    let all_defs = order_defs(all_defs);
+    prg.defs = all_defs.clone();
+
+    if args.lint {
+        for warning in resolve(&prg).shadowing {
+            println!("warning: {}", warning);
+        }
+    }
+
+    if prg.exprs.is_empty() {
+        match check(prg) {
+            Err(e) => println!("{}", e),
+            Ok(_) => *defs = all_defs,
+        }
+
+        return;
+    }
+
+    match derivation(config, prg) {
+        Err(e) => println!("{}", e),
+        Ok(d) => {
+            *defs = all_defs;
+
+            match (args.trace, args.unicode) {
+                (true, true) => println!("{:#}", d),
+                (true, false) => println!("{}", d),
+                (false, true) => println!("{:#}", d.result),
+                (false, false) => println!("{}", d.result),
+            }
+
+            if args.trace {
+                return;
+            }
+
+            if d.outcome != Outcome::Done {
+                println!("{}", d.outcome);
+            }
+        }
+    }
+}
+
+/// Orders definitions so each one follows the definitions it refers to, keeping their order
+/// otherwise. Definitions referring to each other are left in place, to be reported as unbound.
+fn order_defs(defs: Vec<Tag<Def<Loc>, Loc>>) -> Vec<Tag<Def<Loc>, Loc>> {
+    let names: HashSet<String> = defs.iter().map(|def| def.item.name.clone()).collect();
+    let mut pending: Vec<_> = defs
+        .into_iter()
+        .map(|def| {
+            let prog = Program {
+                defs: vec![def.clone()],
+                exprs: Vec::new(),
+                fixities: Vec::new(),
+            };
+
+            // Primitives are left unbound too, a definition named like one hides it from the
+            // definitions that follow.
+            let deps: Vec<String> = resolve(&prog)
+                .unbound
+                .into_iter()
+                .map(|unbound| unbound.name)
+                .filter(|name| names.contains(name))
+                .collect();
+
+            (def, deps)
+        })
+        .collect();
+
+    let mut placed = HashSet::new();
+    let mut ordered = Vec::new();
+
+    while !pending.is_empty() {
+        let next = pending
+            .iter()
+            .position(|(_, deps)| deps.iter().all(|dep| placed.contains(dep)))
+            .unwrap_or(0);
+
+        let (def, _) = pending.remove(next);
+        placed.insert(def.item.name.clone());
+        ordered.push(def);
+    }
+
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(code: &str) -> Vec<String> {
+        order_defs(nebula_core::parse(code).unwrap().defs)
+            .into_iter()
+            .map(|def| def.item.name)
+            .collect()
+    }
+
+    #[test]
+    fn definitions_follow_their_dependencies() {
+        assert_eq!(names("a = b; c = a; b = 2;"), ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn independent_definitions_keep_their_order() {
+        assert_eq!(names("b = 1; a = 2; c = b;"), ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn recursive_definitions_stay_in_place() {
+        assert_eq!(names("f = \\n. f n; a = b; b = a;"), ["f", "a", "b"]);
+    }
+
+    #[test]
+    fn definitions_can_hide_primitives() {
+        assert_eq!(names("a = length 1; length = \\x. x;"), ["length", "a"]);
+    }
+}
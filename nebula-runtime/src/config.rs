@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use crate::{
+    prim::Overflow,
+    strategy::{Form, Strategy},
+};
+
+/// Tells how a program is evaluated.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Config {
+    pub(crate) strategy: Strategy,
+    pub(crate) form: Option<Form>,
+    pub(crate) eta: bool,
+    pub(crate) max_steps: Option<usize>,
+    pub(crate) max_size: Option<usize>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) detect_cycles: bool,
+    pub(crate) pure: bool,
+    pub(crate) overflow: Overflow,
+}
+
+impl Config {
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Form to stop at. Defaults to the form associated to the strategy.
+    pub fn form(mut self, form: Form) -> Self {
+        self.form = Some(form);
+        self
+    }
+
+    /// Also contract eta redexes once no beta redex is left.
+    pub fn eta(mut self, eta: bool) -> Self {
+        self.eta = eta;
+        self
+    }
+
+    /// Stops after that many reduction steps.
+    pub fn max_steps(mut self, steps: usize) -> Self {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    /// Stops as soon as the term holds more than that many nodes.
+    pub fn max_size(mut self, size: usize) -> Self {
+        self.max_size = Some(size);
+        self
+    }
+
+    /// Stops once the evaluation took longer than that.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Stops when a term reduces to a term alpha-equivalent to an earlier one.
+    pub fn detect_cycles(mut self, detect: bool) -> Self {
+        self.detect_cycles = detect;
+        self
+    }
+
+    /// Encodes booleans and conditionals with lambdas, so evaluation only relies on beta
+    /// reduction.
+    pub fn pure(mut self, pure: bool) -> Self {
+        self.pure = pure;
+        self
+    }
+
+    /// What integer arithmetic does when a result doesn't fit in an `i64`.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub(crate) fn target(&self) -> Form {
+        self.form.unwrap_or(self.strategy.default_form())
+    }
+}
@@ -1,28 +1,104 @@
 use nebula_core::{
-    ast::{Expr, Tag},
+    ast::{Def, Expr, Tag},
     register::Register,
-    scope::Scope,
+    scope::{Scope, ScopeTree},
     sym::Literal,
     Loc,
 };
 
-use crate::value::Value;
+use crate::{prim::Prim, reduction::free_variables, value::Value};
+
+/// Generates a definition and registers it in the root scope, where the definition itself, and
+/// the definitions and the expression that follow, can find it. In pure mode, booleans and
+/// conditionals are encoded with lambdas, see [`church`].
+pub fn generate_def(
+    reg: &mut Register<Value>,
+    tree: &mut ScopeTree,
+    pure: bool,
+    def: Tag<Def<Loc>, Loc>,
+) -> eyre::Result<()> {
+    let root = tree.root();
+    let Def { name, value } = def.item;
+
+    // Registered before its value is generated so the definition can refer to itself, which
+    // is fine as definitions are only unfolded once reached.
+    if !reg.register(&root, &name, Value::Uninitialized) {
+        eyre::bail!("{} '{}' is already defined", def.tag, name);
+    }
+
+    tree.bind(&root, &name);
+
+    let value = generate(
+        reg,
+        tree,
+        root.clone(),
+        Tag {
+            item: Box::new(value),
+            tag: def.tag,
+        },
+    )?;
+
+    let value = if pure {
+        church(tree, &root, value)
+    } else {
+        value
+    };
+
+    // Definitions are unfolded anywhere, including under lambdas that could capture a free
+    // variable.
+    if let Some(var) = free_variables(&value).into_iter().min() {
+        eyre::bail!(
+            "{} unbound variable '{}' in the definition of '{}'",
+            def.tag,
+            var,
+            name
+        );
+    }
+
+    reg.register(&root, &name, value);
+
+    Ok(())
+}
 
 pub fn generate(
     reg: &mut Register<Value>,
+    tree: &mut ScopeTree,
     scope: Scope,
     expr: Tag<Box<Tag<Expr<Loc>, Loc>>, Loc>,
 ) -> eyre::Result<Value> {
-    match (*expr.item).item {
-        Expr::Literal(l) => Ok(generate_literal(scope, l)),
-        Expr::Fun(n, b) => generate_fun(reg, scope, n, b),
-        Expr::App(l, r) => generate_app(reg, scope, l, r),
+    let loc = expr.item.tag;
+
+    match expr.item.item {
+        Expr::Literal(l) => Ok(generate_literal(reg, tree, scope, loc, l)),
+        Expr::Fun(n, b) => generate_fun(reg, tree, scope, n, b),
+        Expr::App(l, r) => generate_app(reg, tree, scope, l, r),
+        Expr::Let(n, v, b) => generate_let(reg, tree, scope, n, v, b),
+        Expr::If(c, t, o) => Ok(Value::If {
+            loc: c.tag,
+            cond: Box::new(generate(reg, tree, scope.clone(), c)?),
+            then: Box::new(generate(reg, tree, scope.clone(), t)?),
+            otherwise: Box::new(generate(reg, tree, scope, o)?),
+        }),
+        Expr::Error => eyre::bail!("{} code that failed to parse can't be evaluated", loc),
     }
 }
 
-fn generate_literal(scope: Scope, lit: Literal) -> Value {
+fn generate_literal(
+    reg: &Register<Value>,
+    tree: &ScopeTree,
+    scope: Scope,
+    loc: Loc,
+    lit: Literal,
+) -> Value {
     match lit {
-        Literal::Ref(v) => Value::Var(scope, v),
+        Literal::Ref(v) => match reg.resolve(&scope, &v) {
+            Some(entry) if entry.scope().id() == tree.root().id() => Value::Global(v),
+            Some(_) => Value::Var(scope, v),
+            None => match Prim::from_name(&v) {
+                Some(prim) => Value::Prim(prim, loc),
+                None => Value::Var(scope, v),
+            },
+        },
         Literal::Integer(i) => Value::Integer(i),
         Literal::Double(d) => Value::Double(d),
         Literal::String(s) => Value::String(s),
@@ -33,21 +109,18 @@ fn generate_literal(scope: Scope, lit: Literal) -> Value {
 
 fn generate_fun(
     reg: &mut Register<Value>,
+    tree: &mut ScopeTree,
     scope: Scope,
     name: String,
     body: Tag<Box<Tag<Expr<Loc>, Loc>>, Loc>,
 ) -> eyre::Result<Value> {
-    let scope = scope.inherits();
+    let scope = tree.inherits(&scope);
+    tree.bind(&scope, &name);
 
-    if !reg.register(&scope, &name, Value::Uninitialized) {
-        eyre::bail!(
-            "{} variable '{}' is already introduced in that scope",
-            body.tag,
-            name
-        );
-    }
+    // Shadowing is allowed: every lambda gets its own scope, so the binder can't clash.
+    reg.register(&scope, &name, Value::Uninitialized);
 
-    let body = generate(reg, scope.clone(), body)?;
+    let body = generate(reg, tree, scope.clone(), body)?;
 
     Ok(Value::Fun {
         name: name.to_string(),
@@ -58,16 +131,100 @@ fn generate_fun(
 
 fn generate_app(
     reg: &mut Register<Value>,
+    tree: &mut ScopeTree,
     scope: Scope,
     lhs: Tag<Box<Tag<Expr<Loc>, Loc>>, Loc>,
     rhs: Tag<Box<Tag<Expr<Loc>, Loc>>, Loc>,
 ) -> eyre::Result<Value> {
-    let new_scope = scope.inherits();
-    let lhs = generate(reg, scope, lhs)?;
-    let rhs = generate(reg, new_scope, rhs)?;
+    let lhs = generate(reg, tree, scope.clone(), lhs)?;
+    let rhs = generate(reg, tree, scope, rhs)?;
 
     Ok(Value::App {
         lhs: Box::new(lhs),
         rhs: Box::new(rhs),
     })
 }
+
+/// `let x = value in body` is sugar for `(\x. body value)`.
+fn generate_let(
+    reg: &mut Register<Value>,
+    tree: &mut ScopeTree,
+    scope: Scope,
+    name: String,
+    value: Tag<Box<Tag<Expr<Loc>, Loc>>, Loc>,
+    body: Tag<Box<Tag<Expr<Loc>, Loc>>, Loc>,
+) -> eyre::Result<Value> {
+    let value = generate(reg, tree, scope.clone(), value)?;
+    let fun = generate_fun(reg, tree, scope, name, body)?;
+
+    Ok(Value::App {
+        lhs: Box::new(fun),
+        rhs: Box::new(value),
+    })
+}
+
+/// Replaces booleans by their Church encoding, `true` being `\t. \f. t` and `false` being
+/// `\t. \f. f`, so `if c then a else b` becomes `((c a) b)`. `scope` is the scope the value
+/// was generated in.
+pub fn church(tree: &mut ScopeTree, scope: &Scope, value: Value) -> Value {
+    match value {
+        Value::Bool(b) => {
+            let outer = tree.inherits(scope);
+            tree.bind(&outer, "t");
+            let inner = tree.inherits(&outer);
+            tree.bind(&inner, "f");
+
+            church_bool(b, outer, inner)
+        }
+
+        Value::If {
+            cond,
+            then,
+            otherwise,
+            ..
+        } => Value::App {
+            lhs: Box::new(Value::App {
+                lhs: Box::new(church(tree, scope, *cond)),
+                rhs: Box::new(church(tree, scope, *then)),
+            }),
+            rhs: Box::new(church(tree, scope, *otherwise)),
+        },
+
+        Value::Fun {
+            scope: inner,
+            name,
+            body,
+        } => Value::Fun {
+            body: Box::new(church(tree, &inner, *body)),
+            scope: inner,
+            name,
+        },
+
+        Value::App { lhs, rhs } => Value::App {
+            lhs: Box::new(church(tree, scope, *lhs)),
+            rhs: Box::new(church(tree, scope, *rhs)),
+        },
+
+        x => x,
+    }
+}
+
+/// Church encoding of a boolean, where `t` is bound in the `outer` scope and `f` in the
+/// `inner` one.
+pub(crate) fn church_bool(b: bool, outer: Scope, inner: Scope) -> Value {
+    let body = if b {
+        Value::Var(outer.clone(), "t".to_string())
+    } else {
+        Value::Var(inner.clone(), "f".to_string())
+    };
+
+    Value::Fun {
+        scope: outer,
+        name: "t".to_string(),
+        body: Box::new(Value::Fun {
+            scope: inner,
+            name: "f".to_string(),
+            body: Box::new(body),
+        }),
+    }
+}
@@ -1,31 +1,179 @@
+use config::Config;
 use nebula_core::{
     ast::{Program, Tag},
     register::Register,
-    scope::Scope,
+    scope::ScopeTree,
     Loc,
 };
-use r#gen::generate;
-use reduction::reduction;
+use r#gen::{church, generate, generate_def};
+use step::Derivation;
 use value::Value;
 
+pub mod config;
 pub mod gen;
+pub mod prim;
 pub mod reduction;
+pub mod step;
+pub mod strategy;
 pub mod value;
 
-pub fn derivation(mut prog: Program<Loc>) -> eyre::Result<Value> {
+/// Generates the definitions of the program, and its main expression if any.
+fn generate_program(
+    prog: Program<Loc>,
+    pure: bool,
+) -> eyre::Result<(Register<Value>, Option<Value>)> {
     let mut reg = Register::default();
-    let scope = Scope::new();
-    let root = prog.exprs.pop().unwrap();
-    let root = Tag {
-        item: Box::new(root),
-        tag: Loc { line: 0, col: 0 },
+    let mut tree = ScopeTree::new();
+
+    for def in prog.defs {
+        generate_def(&mut reg, &mut tree, pure, def)?;
+    }
+
+    let main = match prog.exprs.into_iter().last() {
+        None => None,
+        Some(root) => {
+            let scope = tree.root();
+            let root = Tag {
+                tag: root.tag,
+                item: Box::new(root),
+            };
+
+            let main = generate(&mut reg, &mut tree, scope.clone(), root)?;
+
+            if pure {
+                Some(church(&mut tree, &scope, main))
+            } else {
+                Some(main)
+            }
+        }
     };
 
-    let val = generate(&mut reg, scope, root)?;
+    Ok((reg, main))
+}
+
+/// Makes sure the program is well-formed without evaluating it.
+pub fn check(prog: Program<Loc>) -> eyre::Result<()> {
+    generate_program(prog, false).map(|_| ())
+}
+
+pub fn derivation(config: &Config, prog: Program<Loc>) -> eyre::Result<Derivation> {
+    let (reg, main) = generate_program(prog, config.pure)?;
+
+    match main {
+        None => eyre::bail!("nothing to evaluate"),
+        Some(main) => Ok(Derivation::run(config, &reg, main)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategy::Form;
+
+    use super::*;
+
+    const COMBINATORS: &str = "
+        i = \\x. x;
+        k = \\x y. x;
+        s = \\x y z. x z (y z);
+        zero = \\f x. x;
+        succ = \\n f x. f (n f x);
+        plus = \\m n f x. m f (n f x);
+    ";
+
+    fn normal_form(main: &str) -> String {
+        let prog = nebula_core::parse(format!("{} {}", COMBINATORS, main)).unwrap();
+        let derivation = derivation(&Config::default().max_steps(1_000), prog).unwrap();
+
+        assert_eq!(derivation.outcome, step::Outcome::Done);
+        derivation.result.to_string()
+    }
+
+    #[test]
+    fn identity() {
+        assert_eq!(normal_form("i a"), "a");
+    }
+
+    #[test]
+    fn constant() {
+        assert_eq!(normal_form("k a b"), "a");
+    }
+
+    #[test]
+    fn skk_is_identity() {
+        assert_eq!(normal_form("s k k a"), "a");
+        assert_eq!(normal_form("s k k"), "\\z. z");
+    }
+
+    #[test]
+    fn church_successor() {
+        assert_eq!(normal_form("succ zero"), "\\f. \\x. (f x)");
+    }
+
+    #[test]
+    fn church_addition() {
+        assert_eq!(
+            normal_form("plus (succ zero) (succ (succ zero))"),
+            "\\f. \\x. (f (f (f x)))"
+        );
+    }
+
+    #[test]
+    fn reduces_under_lambdas_without_capture() {
+        assert_eq!(normal_form("(\\x y. x) y"), "\\y1. y");
+    }
+
+    #[test]
+    fn lambdas_are_not_named_after_definitions_they_see() {
+        let config = Config::default().form(Form::WeakHeadNormal);
+        let prog = nebula_core::parse("x = 1; (\\y. \\x. y) x").unwrap();
+        let derivation = derivation(&config, prog).unwrap();
+
+        assert_eq!(derivation.result.to_string(), "\\x1. x");
+    }
+
+    #[test]
+    fn let_bindings_see_the_earlier_ones() {
+        assert_eq!(normal_form("let x = 1, y = x + 1 in y * 2"), "4");
+        assert_eq!(normal_form("y * 2 where x = 1, y = x + 1"), "4");
+        assert_eq!(normal_form("let x = 1 in let x = x + 1 in x"), "2");
+    }
+
+    #[test]
+    fn let_values_are_outside_their_binding() {
+        assert_eq!(normal_form("let x = x in x"), "x");
+        assert_eq!(normal_form("y where y = x + 1, x = 1"), "(+ x 1)");
+    }
+
+    #[test]
+    fn innermost_binding_wins() {
+        assert_eq!(normal_form("(\\x. \\x. x) a b"), "b");
+        assert_eq!(normal_form("(\\x. \\y. \\x. y) a b c"), "b");
+    }
 
-    if !val.is_func_application() {
-        eyre::bail!("can only derive top function applications");
+    #[test]
+    fn parentheses_only_group() {
+        assert_eq!(normal_form("(\\f. f (f 1)) g"), "(g (g 1))");
+        assert_eq!(normal_form("(\\x. \\y. x y) y"), "\\y1. (y y1)");
     }
 
-    reduction(&mut reg, val)
+    #[test]
+    fn pure_mode_encodes_computed_booleans() {
+        let config = Config::default().pure(true);
+        let result = |code| {
+            let derivation = derivation(&config, nebula_core::parse(code).unwrap()).unwrap();
+
+            assert_eq!(derivation.outcome, step::Outcome::Done);
+            derivation.result.to_string()
+        };
+
+        assert_eq!(result("if 1 < 2 then 10 else 20"), "10");
+        assert_eq!(result("(\\b. if b then 1 else 2) (3 == 3)"), "1");
+        assert_eq!(result("2 < 1"), "\\t. \\f. f");
+    }
+
+    #[test]
+    fn negative_arguments_read_back() {
+        assert_eq!(normal_form("f (-1)"), "(f (-1))");
+        assert_eq!(normal_form("f (0 - 1.5) 2"), "(f (-1.5) 2)");
+    }
 }
@@ -0,0 +1,785 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use nebula_core::{scope::Scope, Loc};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::value::Value;
+
+/// Operator built into the runtime. A name is only bound to a primitive when no definition or
+/// variable of that name is in scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prim {
+    Add,
+    Sub,
+    Mul,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    /// `(concat a b)` appends string `b` to string `a`.
+    Concat,
+    /// `(length s)` is the number of chars of string `s`.
+    Length,
+    /// `(index s i)` is the char of string `s` at index `i`, starting from `0`.
+    Index,
+    /// `(substring s start len)` is the `len` chars of string `s` found from index `start`.
+    Substring,
+    /// `(ord c)` is the code point of char `c`.
+    Ord,
+    /// `(chr i)` is the char whose code point is `i`.
+    Chr,
+    /// `(show n)` is the string representation of number `n`.
+    Show,
+    /// `(read s)` is the number written in string `s`.
+    Read,
+    /// `(unpack s)` is the Scott-encoded list of the chars of string `s`, see [`scott_list`].
+    Unpack,
+    /// `(pack l)` is the string made of the Scott-encoded list of chars `l`.
+    Pack,
+}
+
+/// What integer arithmetic does when the result doesn't fit in an `i64`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// The evaluation stops with an error.
+    #[default]
+    Checked,
+    /// The result wraps around the bounds.
+    Wrapping,
+    /// The result is clamped to the closest bound.
+    Saturating,
+    /// The result becomes an arbitrary-precision integer.
+    Promote,
+}
+
+#[derive(Clone)]
+enum Number {
+    Integer(i64),
+    Big(BigInt),
+    Double(f64),
+}
+
+/// Whether `name` refers to a primitive when nothing binds it, meant for
+/// [`nebula_core::resolve::resolve_with`].
+pub fn is_primitive(name: &str) -> bool {
+    Prim::from_name(name).is_some()
+}
+
+impl Prim {
+    pub fn from_name(name: &str) -> Option<Prim> {
+        match name {
+            "+" => Some(Prim::Add),
+            "-" => Some(Prim::Sub),
+            "*" => Some(Prim::Mul),
+            "<" => Some(Prim::Lt),
+            "<=" => Some(Prim::Le),
+            ">" => Some(Prim::Gt),
+            ">=" => Some(Prim::Ge),
+            "==" => Some(Prim::Eq),
+            "concat" => Some(Prim::Concat),
+            "length" => Some(Prim::Length),
+            "index" => Some(Prim::Index),
+            "substring" => Some(Prim::Substring),
+            "ord" => Some(Prim::Ord),
+            "chr" => Some(Prim::Chr),
+            "show" => Some(Prim::Show),
+            "read" => Some(Prim::Read),
+            "unpack" => Some(Prim::Unpack),
+            "pack" => Some(Prim::Pack),
+            _ => None,
+        }
+    }
+
+    /// Number of arguments the primitive needs before it can be applied.
+    pub fn arity(self) -> usize {
+        match self {
+            Prim::Length
+            | Prim::Ord
+            | Prim::Chr
+            | Prim::Show
+            | Prim::Read
+            | Prim::Unpack
+            | Prim::Pack => 1,
+            Prim::Substring => 3,
+            _ => 2,
+        }
+    }
+
+    /// Applies the primitive, located at `loc`, to its arguments, which must be in normal form.
+    /// Returns `None` if an argument is neutral, like a variable, as nothing can be computed
+    /// until it is known. Arguments of the wrong type are an error.
+    pub fn apply(
+        self,
+        loc: Loc,
+        overflow: Overflow,
+        args: &[Value],
+    ) -> eyre::Result<Option<Value>> {
+        let args = args.iter().map(Value::unshared).collect::<Vec<_>>();
+
+        if args.iter().any(is_neutral) {
+            return Ok(None);
+        }
+
+        let value = match (self, args.as_slice()) {
+            (
+                Prim::Add
+                | Prim::Sub
+                | Prim::Mul
+                | Prim::Lt
+                | Prim::Le
+                | Prim::Gt
+                | Prim::Ge
+                | Prim::Eq,
+                [lhs, rhs],
+            ) => self.binary(loc, overflow, lhs, rhs)?,
+
+            (Prim::Concat, [Value::String(a), Value::String(b)]) => {
+                Value::String(format!("{}{}", a, b))
+            }
+
+            (Prim::Length, [Value::String(s)]) => Value::Integer(s.chars().count() as i64),
+
+            (Prim::Index, [Value::String(s), Value::Integer(i)]) => {
+                match usize::try_from(*i).ok().and_then(|i| s.chars().nth(i)) {
+                    Some(c) => Value::Char(c),
+                    None => eyre::bail!("{} index {} is out of bounds of \"{}\"", loc, i, s),
+                }
+            }
+
+            (Prim::Substring, [Value::String(s), Value::Integer(start), Value::Integer(len)]) => {
+                let range = usize::try_from(*start)
+                    .ok()
+                    .zip(usize::try_from(*len).ok())
+                    .filter(|(start, len)| start + len <= s.chars().count());
+
+                match range {
+                    Some((start, len)) => Value::String(s.chars().skip(start).take(len).collect()),
+                    None => eyre::bail!(
+                        "{} substring of {} chars from {} is out of bounds of \"{}\"",
+                        loc,
+                        len,
+                        start,
+                        s
+                    ),
+                }
+            }
+
+            (Prim::Ord, [Value::Char(c)]) => Value::Integer(*c as i64),
+
+            (Prim::Chr, [Value::Integer(i)]) => {
+                match u32::try_from(*i).ok().and_then(char::from_u32) {
+                    Some(c) => Value::Char(c),
+                    None => eyre::bail!("{} {} is not a valid char code", loc, i),
+                }
+            }
+
+            (Prim::Show, [n @ (Value::Integer(_) | Value::BigInt(_) | Value::Double(_))]) => {
+                Value::String(n.to_string())
+            }
+
+            (Prim::Read, [Value::String(s)]) => match read_number(s) {
+                Some(Value::BigInt(_)) if overflow != Overflow::Promote => {
+                    eyre::bail!("{} \"{}\" doesn't fit in an integer", loc, s)
+                }
+                Some(n) => n,
+                None => eyre::bail!("{} \"{}\" is not a number", loc, s),
+            },
+
+            (Prim::Unpack, [Value::String(s)]) => s
+                .chars()
+                .rev()
+                .fold(scott_nil(), |tail, c| scott_cons(Value::Char(c), tail)),
+
+            (Prim::Pack, [list]) => {
+                let string = scott_list(list).and_then(|items| {
+                    items
+                        .iter()
+                        .map(|item| match item {
+                            Value::Char(c) => Some(*c),
+                            _ => None,
+                        })
+                        .collect::<Option<String>>()
+                });
+
+                match string {
+                    Some(s) => Value::String(s),
+                    None => return Err(self.mismatch(loc, "a list of chars", &args)),
+                }
+            }
+
+            (_, args) => return Err(self.mismatch(loc, self.expected(), args)),
+        };
+
+        Ok(Some(value))
+    }
+
+    /// Arithmetic and comparison operators.
+    fn binary(self, loc: Loc, overflow: Overflow, lhs: &Value, rhs: &Value) -> eyre::Result<Value> {
+        let mismatch = || self.mismatch(loc, self.expected(), &[lhs.clone(), rhs.clone()]);
+
+        if matches!(self, Prim::Add | Prim::Sub | Prim::Mul) {
+            return match (as_number(lhs), as_number(rhs)) {
+                (Some(a), Some(b)) => self.arithmetic(loc, overflow, a, b),
+                _ => Err(mismatch()),
+            };
+        }
+
+        let ordering = match (lhs, rhs) {
+            (Value::Bool(a), Value::Bool(b)) if self == Prim::Eq => Some(a.cmp(b)),
+            (Value::Char(a), Value::Char(b)) => Some(a.cmp(b)),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            _ => match (as_number(lhs), as_number(rhs)) {
+                (Some(a), Some(b)) => a.compare(&b),
+                _ => return Err(mismatch()),
+            },
+        };
+
+        Ok(Value::Bool(match self {
+            Prim::Lt => ordering == Some(Ordering::Less),
+            Prim::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Prim::Gt => ordering == Some(Ordering::Greater),
+            Prim::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            _ => ordering == Some(Ordering::Equal),
+        }))
+    }
+
+    /// Describes the arguments the primitive expects, for error messages.
+    fn expected(self) -> &'static str {
+        match self {
+            Prim::Add | Prim::Sub | Prim::Mul => "two numbers",
+            Prim::Lt | Prim::Le | Prim::Gt | Prim::Ge => "two numbers, chars or strings",
+            Prim::Eq => "two numbers, booleans, chars or strings",
+            Prim::Concat => "two strings",
+            Prim::Length | Prim::Read | Prim::Unpack => "a string",
+            Prim::Index => "a string and an integer",
+            Prim::Substring => "a string and two integers",
+            Prim::Ord => "a char",
+            Prim::Chr => "an integer",
+            Prim::Show => "a number",
+            Prim::Pack => "a list of chars",
+        }
+    }
+
+    fn mismatch(self, loc: Loc, expected: &str, args: &[Value]) -> eyre::Report {
+        let args = args
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .join(" and ");
+
+        eyre::eyre!("{} '{}' expects {} but got {}", loc, self, expected, args)
+    }
+
+    fn arithmetic(self, loc: Loc, overflow: Overflow, a: Number, b: Number) -> eyre::Result<Value> {
+        match (a, b) {
+            (Number::Integer(a), Number::Integer(b)) => self.integer(loc, overflow, a, b),
+
+            (a @ Number::Double(_), b) | (a, b @ Number::Double(_)) => {
+                let (a, b) = (a.to_f64(), b.to_f64());
+
+                Ok(Value::Double(match self {
+                    Prim::Add => a + b,
+                    Prim::Sub => a - b,
+                    _ => a * b,
+                }))
+            }
+
+            (a, b) => Ok(self.big(a.to_big(), b.to_big())),
+        }
+    }
+
+    fn integer(self, loc: Loc, overflow: Overflow, a: i64, b: i64) -> eyre::Result<Value> {
+        let result = match self {
+            Prim::Add => a.checked_add(b),
+            Prim::Sub => a.checked_sub(b),
+            _ => a.checked_mul(b),
+        };
+
+        if let Some(i) = result {
+            return Ok(Value::Integer(i));
+        }
+
+        match overflow {
+            Overflow::Checked => {
+                eyre::bail!("{} integer overflow in ({} {} {})", loc, self, a, b)
+            }
+
+            Overflow::Wrapping => Ok(Value::Integer(match self {
+                Prim::Add => a.wrapping_add(b),
+                Prim::Sub => a.wrapping_sub(b),
+                _ => a.wrapping_mul(b),
+            })),
+
+            Overflow::Saturating => Ok(Value::Integer(match self {
+                Prim::Add => a.saturating_add(b),
+                Prim::Sub => a.saturating_sub(b),
+                _ => a.saturating_mul(b),
+            })),
+
+            Overflow::Promote => Ok(self.big(BigInt::from(a), BigInt::from(b))),
+        }
+    }
+
+    /// Big integers that fit in an `i64` again are demoted, so every integer has a single
+    /// representation.
+    fn big(self, a: BigInt, b: BigInt) -> Value {
+        let result = match self {
+            Prim::Add => a + b,
+            Prim::Sub => a - b,
+            _ => a * b,
+        };
+
+        match result.to_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::BigInt(result),
+        }
+    }
+}
+
+impl Number {
+    fn to_f64(&self) -> f64 {
+        match self {
+            Number::Integer(i) => *i as f64,
+            Number::Big(b) => b.to_f64().unwrap_or(f64::NAN),
+            Number::Double(d) => *d,
+        }
+    }
+
+    /// Only called on integers.
+    fn to_big(&self) -> BigInt {
+        match self {
+            Number::Integer(i) => BigInt::from(*i),
+            Number::Big(b) => b.clone(),
+            Number::Double(_) => unreachable!(),
+        }
+    }
+
+    fn compare(&self, other: &Number) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Integer(a), Number::Integer(b)) => Some(a.cmp(b)),
+            (Number::Double(_), _) | (_, Number::Double(_)) => {
+                self.to_f64().partial_cmp(&other.to_f64())
+            }
+            (a, b) => Some(a.to_big().cmp(&b.to_big())),
+        }
+    }
+}
+
+fn as_number(value: &Value) -> Option<Number> {
+    match value {
+        Value::Integer(i) => Some(Number::Integer(*i)),
+        Value::BigInt(b) => Some(Number::Big(b.clone())),
+        Value::Double(d) => Some(Number::Double(*d)),
+        _ => None,
+    }
+}
+
+/// Parses an integer, big if it doesn't fit in an `i64`, or a double.
+fn read_number(s: &str) -> Option<Value> {
+    let s = s.trim();
+
+    if let Ok(i) = s.parse::<i64>() {
+        return Some(Value::Integer(i));
+    }
+
+    if let Ok(b) = s.parse::<BigInt>() {
+        return Some(Value::BigInt(b));
+    }
+
+    s.parse::<f64>().ok().map(Value::Double)
+}
+
+fn scott_nil() -> Value {
+    scott(Value::Var(Scope::default(), "n".to_string()))
+}
+
+fn scott_cons(head: Value, tail: Value) -> Value {
+    let cons = Value::Var(Scope::default(), "c".to_string());
+
+    scott(Value::App {
+        lhs: Box::new(Value::App {
+            lhs: Box::new(cons),
+            rhs: Box::new(head),
+        }),
+        rhs: Box::new(tail),
+    })
+}
+
+/// Binds `n` and `c` around the body of a Scott-encoded list.
+fn scott(body: Value) -> Value {
+    Value::Fun {
+        scope: Scope::default(),
+        name: "n".to_string(),
+        body: Box::new(Value::Fun {
+            scope: Scope::default(),
+            name: "c".to_string(),
+            body: Box::new(body),
+        }),
+    }
+}
+
+/// Items of a Scott-encoded list, where `\n. \c. n` is the empty list and
+/// `\n. \c. ((c head) tail)` the list starting with `head` and followed by `tail`.
+pub fn scott_list(value: &Value) -> Option<Vec<Value>> {
+    let mut items = Vec::new();
+    let mut current = value;
+
+    loop {
+        let (nil, cons, body) = match current {
+            Value::Fun {
+                name: nil, body, ..
+            } => match body.as_ref() {
+                Value::Fun {
+                    name: cons, body, ..
+                } => (nil, cons, body.as_ref()),
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        match body {
+            Value::Var(_, var) if var == nil && var != cons => return Some(items),
+
+            Value::App { lhs, rhs } => match lhs.as_ref() {
+                Value::App {
+                    lhs: var,
+                    rhs: head,
+                } if matches!(var.as_ref(), Value::Var(_, var) if var == cons) => {
+                    items.push(head.as_ref().clone());
+                    current = rhs;
+                }
+                _ => return None,
+            },
+
+            _ => return None,
+        }
+    }
+}
+
+/// Tells if a term that can't be reduced further is blocked on a variable, either at its head
+/// or as an argument of a primitive.
+pub(crate) fn is_neutral(value: &Value) -> bool {
+    match value {
+        Value::Var(_, _) => true,
+        Value::App { lhs, rhs } => is_neutral(lhs) || (is_prim_app(lhs) && is_neutral(rhs)),
+        Value::If { cond, .. } => is_neutral(cond),
+        Value::Thunk(thunk) => is_neutral(&thunk.borrow()),
+        _ => false,
+    }
+}
+
+/// Tells if the term is a primitive applied to some of its arguments.
+fn is_prim_app(value: &Value) -> bool {
+    match value {
+        Value::Prim(_, _) => true,
+        Value::App { lhs, .. } => is_prim_app(lhs),
+        Value::Thunk(thunk) => is_prim_app(&thunk.borrow()),
+        _ => false,
+    }
+}
+
+impl Display for Prim {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Prim::Add => "+",
+            Prim::Sub => "-",
+            Prim::Mul => "*",
+            Prim::Lt => "<",
+            Prim::Le => "<=",
+            Prim::Gt => ">",
+            Prim::Ge => ">=",
+            Prim::Eq => "==",
+            Prim::Concat => "concat",
+            Prim::Length => "length",
+            Prim::Index => "index",
+            Prim::Substring => "substring",
+            Prim::Ord => "ord",
+            Prim::Chr => "chr",
+            Prim::Show => "show",
+            Prim::Read => "read",
+            Prim::Unpack => "unpack",
+            Prim::Pack => "pack",
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Overflow::Checked => "checked",
+            Overflow::Wrapping => "wrapping",
+            Overflow::Saturating => "saturating",
+            Overflow::Promote => "promote",
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for Overflow {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checked" => Ok(Overflow::Checked),
+            "wrapping" => Ok(Overflow::Wrapping),
+            "saturating" => Ok(Overflow::Saturating),
+            "promote" => Ok(Overflow::Promote),
+            _ => eyre::bail!(
+                "unknown overflow mode '{}', expected checked, wrapping, saturating or promote",
+                s
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nebula_core::resolve::resolve_with;
+
+    use super::*;
+
+    const LOC: Loc = Loc { line: 1, col: 1 };
+
+    fn apply(prim: Prim, overflow: Overflow, args: &[Value]) -> eyre::Result<String> {
+        Ok(prim.apply(LOC, overflow, args)?.unwrap().to_string())
+    }
+
+    fn int(i: i64) -> Value {
+        Value::Integer(i)
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    fn checked(prim: Prim, args: &[Value]) -> eyre::Result<String> {
+        apply(prim, Overflow::Checked, args)
+    }
+
+    #[test]
+    fn checked_overflow_is_an_error() {
+        let err = apply(Prim::Add, Overflow::Checked, &[int(i64::MAX), int(1)]).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "1:1 integer overflow in (+ 9223372036854775807 1)"
+        );
+    }
+
+    #[test]
+    fn wrapping_overflow() {
+        let result = apply(Prim::Add, Overflow::Wrapping, &[int(i64::MAX), int(1)]);
+        assert_eq!(result.unwrap(), i64::MIN.to_string());
+
+        let result = apply(Prim::Mul, Overflow::Wrapping, &[int(i64::MAX), int(2)]);
+        assert_eq!(result.unwrap(), "-2");
+    }
+
+    #[test]
+    fn saturating_overflow() {
+        let result = apply(Prim::Mul, Overflow::Saturating, &[int(i64::MAX), int(2)]);
+        assert_eq!(result.unwrap(), i64::MAX.to_string());
+
+        let result = apply(Prim::Sub, Overflow::Saturating, &[int(i64::MIN), int(1)]);
+        assert_eq!(result.unwrap(), i64::MIN.to_string());
+    }
+
+    #[test]
+    fn promoted_overflow_is_demoted_when_it_fits_again() {
+        let big = Prim::Add
+            .apply(LOC, Overflow::Promote, &[int(i64::MAX), int(1)])
+            .unwrap()
+            .unwrap();
+        assert!(matches!(big, Value::BigInt(_)));
+        assert_eq!(big.to_string(), "9223372036854775808");
+
+        let result = Prim::Sub.apply(LOC, Overflow::Promote, &[big, int(1)]);
+        assert!(matches!(result.unwrap(), Some(Value::Integer(i64::MAX))));
+    }
+
+    #[test]
+    fn doubles_never_overflow() {
+        let result = apply(
+            Prim::Mul,
+            Overflow::Checked,
+            &[Value::Double(1e308), int(10)],
+        );
+        assert_eq!(result.unwrap(), "inf");
+    }
+
+    #[test]
+    fn read_only_promotes_big_integers_when_asked() {
+        let big = || Value::String("9223372036854775808".to_string());
+
+        let err = apply(Prim::Read, Overflow::Wrapping, &[big()]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:1 \"9223372036854775808\" doesn't fit in an integer"
+        );
+
+        let result = apply(Prim::Read, Overflow::Promote, &[big()]);
+        assert_eq!(result.unwrap(), "9223372036854775808");
+
+        let min = Value::String(i64::MIN.to_string());
+        let result = apply(Prim::Read, Overflow::Checked, &[min]);
+        assert_eq!(result.unwrap(), i64::MIN.to_string());
+    }
+
+    #[test]
+    fn concat_appends_strings() {
+        let result = checked(Prim::Concat, &[string("foo"), string("bar")]);
+        assert_eq!(result.unwrap(), "\"foobar\"");
+
+        let err = checked(Prim::Concat, &[string("foo"), int(1)]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:1 'concat' expects two strings but got \"foo\" and 1"
+        );
+    }
+
+    #[test]
+    fn length_counts_chars() {
+        assert_eq!(checked(Prim::Length, &[string("héllo")]).unwrap(), "5");
+        assert_eq!(checked(Prim::Length, &[string("")]).unwrap(), "0");
+    }
+
+    #[test]
+    fn index_is_bounds_checked() {
+        assert_eq!(
+            checked(Prim::Index, &[string("héllo"), int(1)]).unwrap(),
+            "'é'"
+        );
+
+        let err = checked(Prim::Index, &[string("héllo"), int(5)]).unwrap_err();
+        assert_eq!(err.to_string(), "1:1 index 5 is out of bounds of \"héllo\"");
+
+        let err = checked(Prim::Index, &[string("héllo"), int(-1)]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:1 index -1 is out of bounds of \"héllo\""
+        );
+    }
+
+    #[test]
+    fn substring_is_bounds_checked() {
+        let result = checked(Prim::Substring, &[string("héllo"), int(1), int(3)]);
+        assert_eq!(result.unwrap(), "\"éll\"");
+
+        let result = checked(Prim::Substring, &[string("héllo"), int(5), int(0)]);
+        assert_eq!(result.unwrap(), "\"\"");
+
+        for (start, len) in [(3, 3), (-1, 2), (1, -1)] {
+            let err = checked(Prim::Substring, &[string("héllo"), int(start), int(len)]);
+            assert_eq!(
+                err.unwrap_err().to_string(),
+                format!(
+                    "1:1 substring of {} chars from {} is out of bounds of \"héllo\"",
+                    len, start
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn ord_and_chr_convert_code_points() {
+        assert_eq!(checked(Prim::Ord, &[Value::Char('é')]).unwrap(), "233");
+        assert_eq!(checked(Prim::Chr, &[int(233)]).unwrap(), "'é'");
+
+        for code in [-1, 0xD800, 0x110000] {
+            let err = checked(Prim::Chr, &[int(code)]).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("1:1 {} is not a valid char code", code)
+            );
+        }
+    }
+
+    #[test]
+    fn show_writes_numbers() {
+        assert_eq!(checked(Prim::Show, &[int(-42)]).unwrap(), "\"-42\"");
+        assert_eq!(
+            checked(Prim::Show, &[Value::Double(1.0)]).unwrap(),
+            "\"1.0\""
+        );
+
+        let err = checked(Prim::Show, &[string("42")]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:1 'show' expects a number but got \"42\""
+        );
+    }
+
+    #[test]
+    fn strings_and_chars_compare() {
+        let result = checked(Prim::Lt, &[string("abc"), string("abd")]);
+        assert_eq!(result.unwrap(), "true");
+
+        let result = checked(Prim::Eq, &[string("abc"), string("abc")]);
+        assert_eq!(result.unwrap(), "true");
+
+        let result = checked(Prim::Ge, &[Value::Char('a'), Value::Char('b')]);
+        assert_eq!(result.unwrap(), "false");
+
+        let err = checked(Prim::Lt, &[Value::Char('a'), string("a")]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:1 '<' expects two numbers, chars or strings but got 'a' and \"a\""
+        );
+    }
+
+    #[test]
+    fn pack_undoes_unpack() {
+        for s in ["", "héllo"] {
+            let list = Prim::Unpack
+                .apply(LOC, Overflow::Checked, &[string(s)])
+                .unwrap()
+                .unwrap();
+            let chars = scott_list(&list).unwrap();
+            assert_eq!(chars.len(), s.chars().count());
+
+            let result = checked(Prim::Pack, &[list]);
+            assert_eq!(result.unwrap(), string(s).to_string());
+        }
+    }
+
+    #[test]
+    fn pack_expects_a_list_of_chars() {
+        let list = scott_cons(int(1), scott_nil());
+        let err = checked(Prim::Pack, &[list]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:1 'pack' expects a list of chars but got \\n. \\c. (c 1 \\n. \\c. n)"
+        );
+
+        let err = checked(Prim::Pack, &[string("abc")]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1:1 'pack' expects a list of chars but got \"abc\""
+        );
+    }
+
+    #[test]
+    fn names_round_trip() {
+        let code = "+ - * < <= > >= == concat length index substring ord chr show read unpack pack";
+        for name in code.split(' ') {
+            assert!(is_primitive(name));
+            assert_eq!(Prim::from_name(name).unwrap().to_string(), name);
+        }
+    }
+
+    #[test]
+    fn the_resolver_sees_every_primitive() {
+        let prog = nebula_core::parse("(\\y. y + length \"a\") x").unwrap();
+        let res = resolve_with(&prog, is_primitive);
+        let primitives: Vec<&str> = res.primitives.iter().map(|p| p.name.as_str()).collect();
+
+        assert_eq!(primitives, ["+", "length"]);
+        assert_eq!(res.unbound.len(), 1);
+        assert_eq!(res.unbound[0].name, "x");
+    }
+}
@@ -1,77 +1,339 @@
-use nebula_core::register::Register;
+use std::collections::HashSet;
 
-use crate::value::Value;
+use crate::{
+    step::{Direction, Reduct, Rule},
+    value::Value,
+};
 
-pub fn reduction(reg: &mut Register<Value>, expr: Value) -> eyre::Result<Value> {
-    let expr = eta_reduction(reg, expr);
-    beta_reduction(reg, expr)
-}
-
-fn beta_reduction(reg: &mut Register<Value>, expr: Value) -> eyre::Result<Value> {
+/// Contracts the outermost eta redex of the expression: `\x. (f x)` becomes `f` when `x` does
+/// not occur free in `f`. Returns `None` if there is no such redex.
+pub fn eta_reduction(expr: &Value) -> Option<Reduct> {
     match expr {
-        Value::App { lhs, rhs } => match *lhs {
-            Value::Fun { scope, name, body } => {
-                let body = beta_reduction(reg, *body)?;
-                let rhs = beta_reduction(reg, *rhs)?;
-
-                reg.register(&scope, &name, rhs);
+        Value::Fun { scope, name, body } => {
+            if let Value::App { lhs, rhs } = body.as_ref() {
+                if let Value::Var(_, var) = rhs.as_ref() {
+                    if var == name && !is_free(name, lhs) {
+                        return Some(Reduct::new(Rule::Eta, lhs.as_ref().clone()));
+                    }
+                }
+            }
 
-                Ok(Value::Fun {
-                    scope,
-                    name,
+            eta_reduction(body).map(|reduct| {
+                reduct.within(Direction::Body, |body| Value::Fun {
+                    scope: scope.clone(),
+                    name: name.clone(),
                     body: Box::new(body),
                 })
-            }
+            })
+        }
 
-            Value::Var(s, name) => {
-                let rhs = beta_reduction(reg, *rhs)?;
+        Value::App { lhs, rhs } => {
+            if let Some(reduct) = eta_reduction(lhs) {
+                return Some(reduct.within(Direction::Lhs, |lhs| Value::App {
+                    lhs: Box::new(lhs),
+                    rhs: rhs.clone(),
+                }));
+            }
 
-                Ok(Value::App {
-                    lhs: Box::new(Value::Var(s, name)),
+            eta_reduction(rhs).map(|reduct| {
+                reduct.within(Direction::Rhs, |rhs| Value::App {
+                    lhs: lhs.clone(),
                     rhs: Box::new(rhs),
                 })
+            })
+        }
+
+        Value::Thunk(thunk) => eta_reduction(&thunk.borrow()),
+
+        _ => None,
+    }
+}
+
+/// Replaces every free occurrence of `name` in `expr` by `arg`. Bound variables of `expr` are
+/// renamed whenever they would capture a free variable of `arg`, or share the name of a
+/// definition it refers to, which would print as if it was captured.
+pub fn substitute(expr: &Value, name: &str, arg: &Value) -> Value {
+    let mut arg_vars = free_variables(arg);
+    collect_globals(arg, &mut arg_vars);
+
+    substitute_with(expr, name, arg, &arg_vars)
+}
+
+fn substitute_with(expr: &Value, name: &str, arg: &Value, arg_vars: &HashSet<String>) -> Value {
+    match expr {
+        Value::Var(_, var) if var == name => arg.clone(),
+
+        Value::App { lhs, rhs } => Value::App {
+            lhs: Box::new(substitute_with(lhs, name, arg, arg_vars)),
+            rhs: Box::new(substitute_with(rhs, name, arg, arg_vars)),
+        },
+
+        Value::Fun {
+            scope,
+            name: bound,
+            body,
+        } => {
+            // The binder shadows `name`, or `name` doesn't appear in the body at all.
+            if bound == name || !is_free(name, body) {
+                return expr.clone();
+            }
+
+            if !arg_vars.contains(bound) {
+                return Value::Fun {
+                    scope: scope.clone(),
+                    name: bound.clone(),
+                    body: Box::new(substitute_with(body, name, arg, arg_vars)),
+                };
             }
 
-            _ => eyre::bail!("exception: expected a function or a variable"),
+            let mut avoid = free_variables(body);
+            collect_globals(body, &mut avoid);
+            avoid.extend(arg_vars.iter().cloned());
+            avoid.insert(name.to_string());
+
+            let fresh = fresh_name(bound, &avoid);
+            let body = substitute(body, bound, &Value::Var(scope.clone(), fresh.clone()));
+
+            Value::Fun {
+                scope: scope.clone(),
+                name: fresh,
+                body: Box::new(substitute_with(&body, name, arg, arg_vars)),
+            }
+        }
+
+        Value::If {
+            cond,
+            then,
+            otherwise,
+            loc,
+        } => Value::If {
+            cond: Box::new(substitute_with(cond, name, arg, arg_vars)),
+            then: Box::new(substitute_with(then, name, arg, arg_vars)),
+            otherwise: Box::new(substitute_with(otherwise, name, arg, arg_vars)),
+            loc: *loc,
         },
 
-        x => Ok(x),
+        // A shared term is only copied when the substitution actually changes it.
+        Value::Thunk(thunk) if is_free(name, &thunk.borrow()) => {
+            Value::thunk(substitute_with(&thunk.borrow(), name, arg, arg_vars))
+        }
+
+        x => x.clone(),
     }
 }
 
-fn eta_reduction(reg: &mut Register<Value>, expr: Value) -> Value {
+/// Returns the set of variables occurring free in the expression.
+pub fn free_variables(expr: &Value) -> HashSet<String> {
+    let mut vars = HashSet::new();
+    collect_free_variables(expr, &mut Vec::new(), &mut vars);
+
+    vars
+}
+
+fn collect_free_variables<'a>(
+    expr: &'a Value,
+    bound: &mut Vec<&'a str>,
+    vars: &mut HashSet<String>,
+) {
     match expr {
-        Value::Fun { scope, name, body } => {
-            let body = eta_reduction(reg, *body);
-
-            if let Value::App { lhs, rhs } = body {
-                match *rhs {
-                    Value::Var(scope, var) => {
-                        if name == var {
-                            reg.remove(&scope, &name);
-                            return *lhs;
-                        } else {
-                            Value::App {
-                                lhs,
-                                rhs: Box::new(Value::Var(scope, var)),
-                            }
-                        }
-                    }
+        Value::Var(_, name) if !bound.contains(&name.as_str()) => {
+            vars.insert(name.clone());
+        }
 
-                    x => Value::App {
-                        lhs,
-                        rhs: Box::new(x),
-                    },
-                }
-            } else {
-                Value::Fun {
-                    scope,
-                    name,
-                    body: Box::new(body),
+        Value::Fun { name, body, .. } => {
+            bound.push(name);
+            collect_free_variables(body, bound, vars);
+            bound.pop();
+        }
+
+        Value::App { lhs, rhs } => {
+            collect_free_variables(lhs, bound, vars);
+            collect_free_variables(rhs, bound, vars);
+        }
+
+        Value::If {
+            cond,
+            then,
+            otherwise,
+            ..
+        } => {
+            collect_free_variables(cond, bound, vars);
+            collect_free_variables(then, bound, vars);
+            collect_free_variables(otherwise, bound, vars);
+        }
+
+        Value::Thunk(thunk) => {
+            for var in free_variables(&thunk.borrow()) {
+                if !bound.contains(&var.as_str()) {
+                    vars.insert(var);
                 }
             }
         }
 
-        x => x,
+        _ => {}
+    }
+}
+
+/// Adds the definitions the expression refers to.
+fn collect_globals(expr: &Value, names: &mut HashSet<String>) {
+    match expr {
+        Value::Global(name) => {
+            names.insert(name.clone());
+        }
+
+        Value::Fun { body, .. } => collect_globals(body, names),
+
+        Value::App { lhs, rhs } => {
+            collect_globals(lhs, names);
+            collect_globals(rhs, names);
+        }
+
+        Value::If {
+            cond,
+            then,
+            otherwise,
+            ..
+        } => {
+            collect_globals(cond, names);
+            collect_globals(then, names);
+            collect_globals(otherwise, names);
+        }
+
+        Value::Thunk(thunk) => collect_globals(&thunk.borrow(), names),
+
+        _ => {}
+    }
+}
+
+/// Tells if `name` occurs free in the expression.
+pub fn is_free(name: &str, expr: &Value) -> bool {
+    match expr {
+        Value::Var(_, var) => var == name,
+        Value::Fun {
+            name: bound, body, ..
+        } => bound != name && is_free(name, body),
+        Value::App { lhs, rhs } => is_free(name, lhs) || is_free(name, rhs),
+        Value::If {
+            cond,
+            then,
+            otherwise,
+            ..
+        } => is_free(name, cond) || is_free(name, then) || is_free(name, otherwise),
+        Value::Thunk(thunk) => is_free(name, &thunk.borrow()),
+        _ => false,
+    }
+}
+
+/// Generates a variable name derived from `name` that doesn't belong to `avoid`.
+fn fresh_name(name: &str, avoid: &HashSet<String>) -> String {
+    let base = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    let base = if base.is_empty() { name } else { base };
+
+    (1..)
+        .map(|i| format!("{}{}", base, i))
+        .find(|candidate| !avoid.contains(candidate))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use nebula_core::scope::Scope;
+
+    use super::*;
+
+    fn var(name: &str) -> Value {
+        Value::Var(Scope::default(), name.to_string())
+    }
+
+    fn fun(name: &str, body: Value) -> Value {
+        Value::Fun {
+            scope: Scope::default(),
+            name: name.to_string(),
+            body: Box::new(body),
+        }
+    }
+
+    fn app(lhs: Value, rhs: Value) -> Value {
+        Value::App {
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    #[test]
+    fn substitute_replaces_free_occurrences() {
+        let expr = app(app(var("x"), var("y")), var("x"));
+
+        assert_eq!(substitute(&expr, "x", &var("z")).to_string(), "(z y z)");
+    }
+
+    #[test]
+    fn substitute_leaves_bound_occurrences() {
+        let expr = app(fun("x", var("x")), var("x"));
+
+        assert_eq!(
+            substitute(&expr, "x", &var("z")).to_string(),
+            "((\\x. x) z)"
+        );
+    }
+
+    #[test]
+    fn substitute_renames_capturing_binders() {
+        let expr = fun("y", app(var("x"), var("y")));
+
+        assert_eq!(
+            substitute(&expr, "x", &var("y")).to_string(),
+            "\\y1. (y y1)"
+        );
+    }
+
+    #[test]
+    fn fresh_names_avoid_every_free_variable() {
+        let expr = fun("y", app(app(var("x"), var("y")), var("y1")));
+
+        assert_eq!(
+            substitute(&expr, "x", &var("y")).to_string(),
+            "\\y2. (y y2 y1)"
+        );
+    }
+
+    #[test]
+    fn binders_are_not_named_after_substituted_definitions() {
+        let expr = fun("x", var("y"));
+        let global = Value::Global("x".to_string());
+
+        assert_eq!(substitute(&expr, "y", &global).to_string(), "\\x1. x");
+    }
+
+    #[test]
+    fn fresh_names_avoid_definitions_of_the_body() {
+        let expr = fun(
+            "y",
+            app(app(var("x"), var("y")), Value::Global("y1".to_string())),
+        );
+
+        assert_eq!(
+            substitute(&expr, "x", &var("y")).to_string(),
+            "\\y2. (y y2 y1)"
+        );
+    }
+
+    #[test]
+    fn free_variables_skip_bound_ones() {
+        let expr = fun("x", app(app(var("x"), var("y")), fun("z", var("z"))));
+        let vars = free_variables(&expr);
+
+        assert_eq!(vars, HashSet::from(["y".to_string()]));
+        assert!(is_free("y", &expr));
+        assert!(!is_free("x", &expr));
+    }
+
+    #[test]
+    fn eta_reduction_requires_the_variable_not_free() {
+        let redex = fun("x", app(var("f"), var("x")));
+        let not_redex = fun("x", app(app(var("x"), var("f")), var("x")));
+
+        assert_eq!(eta_reduction(&redex).unwrap().value.to_string(), "f");
+        assert!(eta_reduction(&not_redex).is_none());
     }
 }
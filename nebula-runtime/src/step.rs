@@ -0,0 +1,410 @@
+use std::{collections::HashMap, fmt::Display, time::Instant};
+
+use nebula_core::register::Register;
+
+use crate::{
+    config::Config,
+    reduction::eta_reduction,
+    strategy::{Env, Form},
+    value::Value,
+};
+
+/// Reduction rule used to contract a redex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// `(\x. M) N` becomes `M[x := N]`.
+    Beta,
+    /// `\x. (M x)` becomes `M` when `x` doesn't occur free in `M`.
+    Eta,
+    /// Unfolding of a definition, application of a primitive or selection of a branch.
+    Delta,
+}
+
+/// One move from a term to one of its direct sub-terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Body of a lambda.
+    Body,
+    /// Function of an application.
+    Lhs,
+    /// Argument of an application.
+    Rhs,
+    /// Condition of a conditional.
+    Cond,
+}
+
+/// Result of contracting a redex, before it's turned into a [`Step`].
+pub struct Reduct {
+    pub(crate) value: Value,
+    pub(crate) rule: Rule,
+    /// Path from the redex up to the root, in reverse order.
+    pub(crate) path: Vec<Direction>,
+}
+
+impl Reduct {
+    pub(crate) fn new(rule: Rule, value: Value) -> Self {
+        Self {
+            value,
+            rule,
+            path: Vec::new(),
+        }
+    }
+
+    /// Rebuilds the parent of the term that contains the redex.
+    pub(crate) fn within<F>(mut self, direction: Direction, rebuild: F) -> Self
+    where
+        F: FnOnce(Value) -> Value,
+    {
+        self.path.push(direction);
+        self.value = rebuild(self.value);
+        self
+    }
+}
+
+/// A single reduction step.
+#[derive(Clone)]
+pub struct Step {
+    pub rule: Rule,
+    /// Path from the root of the term to the contracted redex.
+    pub path: Vec<Direction>,
+    pub before: Value,
+    pub after: Value,
+}
+
+impl Step {
+    /// The redex that was contracted.
+    pub fn redex(&self) -> Option<&Value> {
+        self.before.at(&self.path)
+    }
+
+    /// What the redex was contracted into.
+    pub fn contractum(&self) -> Option<&Value> {
+        self.after.at(&self.path)
+    }
+}
+
+/// Reduces a term one step at a time.
+pub struct Stepper<'a> {
+    config: Config,
+    defs: &'a Register<Value>,
+    form: Form,
+    current: Value,
+}
+
+impl<'a> Stepper<'a> {
+    /// Definitions referenced by the term are looked up in `defs`.
+    pub fn new(config: &Config, defs: &'a Register<Value>, expr: Value) -> Self {
+        Self {
+            config: *config,
+            defs,
+            form: config.target(),
+            current: expr,
+        }
+    }
+
+    pub fn current(&self) -> &Value {
+        &self.current
+    }
+
+    pub fn into_current(self) -> Value {
+        self.current
+    }
+
+    /// Contracts the next redex. Returns `None` once the term reached the requested form.
+    pub fn step(&mut self) -> eyre::Result<Option<Step>> {
+        let before = self.current.unshared();
+        let env = Env {
+            defs: self.defs,
+            overflow: self.config.overflow,
+            pure: self.config.pure,
+        };
+
+        let mut reduct = self.config.strategy.step(&env, &self.current, self.form)?;
+
+        if reduct.is_none() && self.config.eta && self.form != Form::WeakHeadNormal {
+            reduct = eta_reduction(&self.current);
+        }
+
+        let mut reduct = match reduct {
+            None => return Ok(None),
+            Some(reduct) => reduct,
+        };
+
+        reduct.path.reverse();
+        self.current = reduct.value;
+
+        Ok(Some(Step {
+            rule: reduct.rule,
+            path: reduct.path,
+            before,
+            after: self.current.unshared(),
+        }))
+    }
+}
+
+/// Limit an evaluation ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fuel {
+    Steps,
+    Size,
+    Time,
+}
+
+/// How an evaluation ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The term reached the requested form.
+    Done,
+    /// The evaluation was stopped by one of the configured limits.
+    OutOfFuel(Fuel),
+    /// The term after the last step is alpha-equivalent to the one found after the step at
+    /// that index (`0` being the initial term), so the evaluation would never end.
+    Cycle(usize),
+    /// The evaluation can't go on, like when a primitive gets arguments of the wrong type.
+    Error(String),
+}
+
+/// Every step taken to reduce a term.
+pub struct Derivation {
+    pub initial: Value,
+    pub steps: Vec<Step>,
+    /// Final term, or the last term reached if the evaluation didn't complete.
+    pub result: Value,
+    pub outcome: Outcome,
+}
+
+impl Derivation {
+    /// Reduces the term until it reaches the form requested by the configuration, or one of
+    /// the configured limits is hit.
+    pub fn run(config: &Config, defs: &Register<Value>, expr: Value) -> Self {
+        let start = Instant::now();
+        let initial = expr.unshared();
+        let mut stepper = Stepper::new(config, defs, expr);
+        let mut steps = Vec::new();
+        let mut seen = HashMap::new();
+
+        if config.detect_cycles {
+            seen.insert(initial.alpha_key(), 0);
+        }
+
+        let outcome = loop {
+            if config.max_steps.is_some_and(|max| steps.len() >= max) {
+                break Outcome::OutOfFuel(Fuel::Steps);
+            }
+
+            if config.timeout.is_some_and(|max| start.elapsed() >= max) {
+                break Outcome::OutOfFuel(Fuel::Time);
+            }
+
+            let step = match stepper.step() {
+                Err(e) => break Outcome::Error(e.to_string()),
+                Ok(None) => break Outcome::Done,
+                Ok(Some(step)) => step,
+            };
+
+            let too_big = config.max_size.is_some_and(|max| step.after.size() > max);
+            let repeats = if config.detect_cycles {
+                seen.insert(step.after.alpha_key(), steps.len() + 1)
+            } else {
+                None
+            };
+
+            steps.push(step);
+
+            if too_big {
+                break Outcome::OutOfFuel(Fuel::Size);
+            }
+
+            if let Some(index) = repeats {
+                break Outcome::Cycle(index);
+            }
+        };
+
+        Self {
+            initial,
+            steps,
+            result: stepper.into_current().unshared(),
+            outcome,
+        }
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Rule::Beta => "β",
+            Rule::Eta => "η",
+            Rule::Delta => "δ",
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+/// Terms are printed in the same form as the derivation, `{:#}` printing lambdas with `λ`.
+impl Display for Derivation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "   ")?;
+        self.initial.fmt(f)?;
+
+        for step in self.steps.iter() {
+            write!(f, "\n→{} ", step.rule)?;
+            step.after.fmt(f)?;
+        }
+
+        if self.outcome != Outcome::Done {
+            write!(f, "\n{}", self.outcome)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Done => write!(f, "done"),
+            Outcome::OutOfFuel(Fuel::Steps) => write!(f, "out of fuel: too many steps"),
+            Outcome::OutOfFuel(Fuel::Size) => write!(f, "out of fuel: term is too big"),
+            Outcome::OutOfFuel(Fuel::Time) => write!(f, "out of fuel: took too long"),
+            Outcome::Cycle(0) => write!(f, "diverges: reduces back to the initial term"),
+            Outcome::Cycle(index) => {
+                write!(f, "diverges: reduces back to the term after step {}", index)
+            }
+            Outcome::Error(e) => write!(f, "error: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::derivation;
+
+    use super::*;
+
+    const OMEGA: &str = "(\\x. x x) (\\x. x x)";
+
+    fn run(config: Config, code: &str) -> Derivation {
+        derivation(&config, nebula_core::parse(code).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn omega_reduces_back_to_itself() {
+        let derivation = run(Config::default().detect_cycles(true), OMEGA);
+
+        assert_eq!(derivation.outcome, Outcome::Cycle(0));
+        assert_eq!(derivation.steps.len(), 1);
+    }
+
+    #[test]
+    fn cycles_are_detected_up_to_alpha_equivalence() {
+        let derivation = run(
+            Config::default().detect_cycles(true),
+            "(\\x. x x) (\\y. y y)",
+        );
+
+        assert_eq!(derivation.outcome, Outcome::Cycle(0));
+        assert_eq!(derivation.result.to_string(), "((\\y. (y y)) \\y. (y y))");
+    }
+
+    #[test]
+    fn steps_run_out() {
+        let derivation = run(Config::default().max_steps(5), OMEGA);
+
+        assert_eq!(derivation.outcome, Outcome::OutOfFuel(Fuel::Steps));
+        assert_eq!(derivation.steps.len(), 5);
+        assert_eq!(derivation.result.to_string(), "((\\x. (x x)) \\x. (x x))");
+    }
+
+    #[test]
+    fn size_runs_out() {
+        let code = "(\\x. x x x) (\\x. x x x)";
+        let derivation = run(Config::default().max_steps(100).max_size(20), code);
+
+        assert_eq!(derivation.outcome, Outcome::OutOfFuel(Fuel::Size));
+        assert!(derivation.result.size() > 20);
+    }
+
+    #[test]
+    fn time_runs_out() {
+        let derivation = run(Config::default().timeout(Duration::ZERO), OMEGA);
+
+        assert_eq!(derivation.outcome, Outcome::OutOfFuel(Fuel::Time));
+        assert!(derivation.steps.is_empty());
+    }
+
+    #[test]
+    fn steps_locate_their_redex() {
+        let derivation = run(Config::default(), "\\y. (\\x. x) y");
+        let step = &derivation.steps[0];
+
+        assert_eq!(step.rule, Rule::Beta);
+        assert_eq!(step.path, vec![Direction::Body]);
+        assert_eq!(step.redex().unwrap().to_string(), "((\\x. x) y)");
+        assert_eq!(step.contractum().unwrap().to_string(), "y");
+    }
+
+    fn rules(derivation: &Derivation) -> Vec<Rule> {
+        derivation.steps.iter().map(|step| step.rule).collect()
+    }
+
+    #[test]
+    fn steps_locate_nested_redexes() {
+        let derivation = run(Config::default(), "f a ((\\x. x) b)");
+        let step = &derivation.steps[0];
+
+        assert_eq!(step.path, vec![Direction::Rhs]);
+        assert_eq!(step.redex().unwrap().to_string(), "((\\x. x) b)");
+        assert_eq!(step.contractum().unwrap().to_string(), "b");
+        assert_eq!(step.before.to_string(), "(f a ((\\x. x) b))");
+        assert_eq!(step.after.to_string(), "(f a b)");
+    }
+
+    #[test]
+    fn steps_report_their_rule() {
+        assert_eq!(rules(&run(Config::default(), "(\\x. x) a")), [Rule::Beta]);
+        assert_eq!(
+            rules(&run(Config::default().eta(true), "\\x. f x")),
+            [Rule::Eta]
+        );
+        assert_eq!(rules(&run(Config::default(), "1 + 2")), [Rule::Delta]);
+        assert_eq!(
+            rules(&run(Config::default(), "if true then a else b")),
+            [Rule::Delta]
+        );
+        assert_eq!(
+            rules(&run(Config::default(), "i = \\x. x; i a")),
+            [Rule::Delta, Rule::Beta]
+        );
+    }
+
+    #[test]
+    fn eta_is_off_by_default() {
+        let derivation = run(Config::default(), "\\x. f x");
+
+        assert!(derivation.steps.is_empty());
+        assert_eq!(derivation.result.to_string(), "\\x. (f x)");
+    }
+
+    #[test]
+    fn derivations_can_be_replayed() {
+        let derivation = run(Config::default(), "(\\x y. y x) ((\\z. z) a) b");
+
+        let mut current = derivation.initial.to_string();
+        for step in derivation.steps.iter() {
+            assert_eq!(step.before.to_string(), current);
+            current = step.after.to_string();
+        }
+
+        assert_eq!(current, derivation.result.to_string());
+        assert_eq!(
+            derivation.to_string(),
+            "   ((\\x. \\y. (y x)) ((\\z. z) a) b)\n\
+             →β ((\\y. (y ((\\z. z) a))) b)\n\
+             →β (b ((\\z. z) a))\n\
+             →β (b a)"
+        );
+    }
+}
@@ -0,0 +1,517 @@
+use std::{fmt::Display, str::FromStr};
+
+use nebula_core::{register::Register, scope::Scope, Loc};
+
+use crate::{
+    prim::{is_neutral, Overflow, Prim},
+    r#gen::church_bool,
+    reduction::substitute,
+    step::{Direction, Reduct, Rule},
+    value::Value,
+};
+
+/// Order in which redexes are contracted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Leftmost-outermost redex first, reducing under lambdas.
+    #[default]
+    NormalOrder,
+    /// Leftmost-innermost redex first: the operator and the argument are brought to normal
+    /// form before a redex is contracted.
+    ApplicativeOrder,
+    /// Leftmost-outermost redex first, arguments are substituted unevaluated.
+    CallByName,
+    /// Arguments are reduced to weak head normal form before being substituted.
+    CallByValue,
+    /// Like call-by-name, but an argument is reduced at most once and the result is shared
+    /// by all its occurrences.
+    CallByNeed,
+}
+
+/// How far a term is reduced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Form {
+    /// No redex left anywhere in the term.
+    Normal,
+    /// No redex left in head position, even under lambdas.
+    HeadNormal,
+    /// Either a lambda or an application whose head can't be reduced.
+    WeakHeadNormal,
+}
+
+/// What reducing a term depends on, besides the term itself.
+#[derive(Clone, Copy)]
+pub struct Env<'a> {
+    /// Definitions, looked up in the root scope.
+    pub defs: &'a Register<Value>,
+    pub overflow: Overflow,
+    /// Booleans computed by primitives are Church-encoded, like the ones of the program.
+    pub pure: bool,
+}
+
+impl Strategy {
+    /// Form a term is reduced to when none was asked explicitly.
+    pub fn default_form(self) -> Form {
+        match self {
+            Strategy::NormalOrder | Strategy::ApplicativeOrder => Form::Normal,
+            Strategy::CallByName | Strategy::CallByValue | Strategy::CallByNeed => {
+                Form::WeakHeadNormal
+            }
+        }
+    }
+
+    /// Form the function of an application is reduced to before being applied.
+    fn operator_form(self) -> Form {
+        match self {
+            Strategy::ApplicativeOrder => Form::Normal,
+            _ => Form::WeakHeadNormal,
+        }
+    }
+
+    /// Form the argument of an application is reduced to before being substituted, if any.
+    fn operand_form(self) -> Option<Form> {
+        match self {
+            Strategy::ApplicativeOrder => Some(Form::Normal),
+            Strategy::CallByValue => Some(Form::WeakHeadNormal),
+            _ => None,
+        }
+    }
+
+    /// Contracts the next redex of the expression according to that strategy. Returns `None` if
+    /// the expression is already in the requested form, and an error if a primitive is applied
+    /// to arguments of the wrong type.
+    pub fn step(self, env: &Env, expr: &Value, form: Form) -> eyre::Result<Option<Reduct>> {
+        match expr {
+            Value::App { lhs, rhs } => self.step_app(env, lhs, rhs, form),
+
+            Value::If {
+                cond,
+                then,
+                otherwise,
+                loc,
+            } => self.step_if(env, cond, then, otherwise, *loc, form),
+
+            Value::Global(name) => Ok(env
+                .defs
+                .lookup(&Scope::new(), name)
+                .map(|value| Reduct::new(Rule::Delta, value.clone()))),
+
+            Value::Fun { scope, name, body } => {
+                if form == Form::WeakHeadNormal {
+                    return Ok(None);
+                }
+
+                Ok(self.step(env, body, form)?.map(|reduct| {
+                    reduct.within(Direction::Body, |body| Value::Fun {
+                        scope: scope.clone(),
+                        name: name.clone(),
+                        body: Box::new(body),
+                    })
+                }))
+            }
+
+            // Reducing a shared term updates it in place, so every other occurrence benefits
+            // from that step.
+            Value::Thunk(thunk) => {
+                let reduct = self.step(env, &thunk.borrow(), form)?;
+
+                Ok(reduct.map(|mut reduct| {
+                    *thunk.borrow_mut() = std::mem::replace(&mut reduct.value, expr.clone());
+                    reduct
+                }))
+            }
+
+            _ => Ok(None),
+        }
+    }
+
+    fn step_app(
+        self,
+        env: &Env,
+        lhs: &Value,
+        rhs: &Value,
+        form: Form,
+    ) -> eyre::Result<Option<Reduct>> {
+        let in_lhs = |reduct: Reduct| {
+            reduct.within(Direction::Lhs, |lhs| Value::App {
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs.clone()),
+            })
+        };
+
+        let in_rhs = |reduct: Reduct| {
+            reduct.within(Direction::Rhs, |rhs| Value::App {
+                lhs: Box::new(lhs.clone()),
+                rhs: Box::new(rhs),
+            })
+        };
+
+        if let Some((prim, loc, arity)) = prim_head(lhs) {
+            if arity == 1 {
+                let mut args = Vec::new();
+                collect_args(lhs, &mut args);
+                args.push(rhs.clone());
+
+                return self.step_prim(env, prim, loc, &args);
+            }
+        }
+
+        if let Some(reduct) = self.step(env, lhs, self.operator_form())? {
+            return Ok(Some(in_lhs(reduct)));
+        }
+
+        if is_fun(lhs) {
+            if let Some(operand) = self.operand_form() {
+                if let Some(reduct) = self.step(env, rhs, operand)? {
+                    return Ok(Some(in_rhs(reduct)));
+                }
+            }
+
+            return Ok(Some(Reduct::new(Rule::Beta, self.contract(lhs, rhs))));
+        }
+
+        // The head of the application is stuck, only a normal form requires going further.
+        if form != Form::Normal {
+            return Ok(None);
+        }
+
+        if let Some(reduct) = self.step(env, lhs, Form::Normal)? {
+            return Ok(Some(in_lhs(reduct)));
+        }
+
+        Ok(self.step(env, rhs, Form::Normal)?.map(in_rhs))
+    }
+
+    /// Reduces the arguments of a saturated primitive to normal form, from left to right, then
+    /// applies it.
+    fn step_prim(
+        self,
+        env: &Env,
+        prim: Prim,
+        loc: Loc,
+        args: &[Value],
+    ) -> eyre::Result<Option<Reduct>> {
+        for (index, arg) in args.iter().enumerate() {
+            if let Some(reduct) = self.step(env, arg, Form::Normal)? {
+                let head = args[..index]
+                    .iter()
+                    .fold(Value::Prim(prim, loc), |lhs, arg| app(lhs, arg.clone()));
+
+                let mut reduct = reduct.within(Direction::Rhs, |arg| app(head, arg));
+
+                for arg in args[index + 1..].iter() {
+                    reduct = reduct.within(Direction::Lhs, |lhs| app(lhs, arg.clone()));
+                }
+
+                return Ok(Some(reduct));
+            }
+        }
+
+        Ok(prim
+            .apply(loc, env.overflow, args)?
+            .map(|value| match value {
+                Value::Bool(b) if env.pure => church_bool(b, Scope::new(), Scope::new()),
+                value => value,
+            })
+            .map(|value| Reduct::new(Rule::Delta, value)))
+    }
+
+    fn step_if(
+        self,
+        env: &Env,
+        cond: &Value,
+        then: &Value,
+        otherwise: &Value,
+        loc: Loc,
+        form: Form,
+    ) -> eyre::Result<Option<Reduct>> {
+        let in_cond = |reduct: Reduct| {
+            reduct.within(Direction::Cond, |cond| Value::If {
+                cond: Box::new(cond),
+                then: Box::new(then.clone()),
+                otherwise: Box::new(otherwise.clone()),
+                loc,
+            })
+        };
+
+        if let Some(reduct) = self.step(env, cond, Form::WeakHeadNormal)? {
+            return Ok(Some(in_cond(reduct)));
+        }
+
+        match as_bool(cond) {
+            Some(true) => Ok(Some(Reduct::new(Rule::Delta, then.clone()))),
+            Some(false) => Ok(Some(Reduct::new(Rule::Delta, otherwise.clone()))),
+
+            // The branches of a conditional stuck on a variable are left alone: reducing a
+            // branch that would never be selected could diverge.
+            None if is_neutral(cond) => match form {
+                Form::Normal => Ok(self.step(env, cond, Form::Normal)?.map(in_cond)),
+                _ => Ok(None),
+            },
+
+            None => eyre::bail!("{} 'if' expects a boolean but got {}", loc, cond.unshared()),
+        }
+    }
+
+    fn contract(self, fun: &Value, arg: &Value) -> Value {
+        match fun {
+            Value::Thunk(thunk) => self.contract(&thunk.borrow(), arg),
+
+            Value::Fun { name, body, .. } => {
+                if self != Strategy::CallByNeed || !is_reducible(arg) {
+                    return substitute(body, name, arg);
+                }
+
+                substitute(body, name, &Value::thunk(arg.clone()))
+            }
+
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn is_fun(expr: &Value) -> bool {
+    match expr {
+        Value::Fun { .. } => true,
+        Value::Thunk(thunk) => is_fun(&thunk.borrow()),
+        _ => false,
+    }
+}
+
+fn app(lhs: Value, rhs: Value) -> Value {
+    Value::App {
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    }
+}
+
+/// Primitive found at the head of the application spine, along with the number of arguments
+/// it still expects. Shared terms along the spine are looked through.
+fn prim_head(expr: &Value) -> Option<(Prim, Loc, usize)> {
+    match expr {
+        Value::Prim(prim, loc) => Some((*prim, *loc, prim.arity())),
+        Value::App { lhs, .. } => match prim_head(lhs)? {
+            (_, _, 0) => None,
+            (prim, loc, arity) => Some((prim, loc, arity - 1)),
+        },
+        Value::Thunk(thunk) => prim_head(&thunk.borrow()),
+        _ => None,
+    }
+}
+
+fn collect_args(expr: &Value, args: &mut Vec<Value>) {
+    match expr {
+        Value::App { lhs, rhs } => {
+            collect_args(lhs, args);
+            args.push(rhs.as_ref().clone());
+        }
+
+        Value::Thunk(thunk) => collect_args(&thunk.borrow(), args),
+
+        _ => {}
+    }
+}
+
+fn as_bool(expr: &Value) -> Option<bool> {
+    match expr {
+        Value::Bool(b) => Some(*b),
+        Value::Thunk(thunk) => as_bool(&thunk.borrow()),
+        _ => None,
+    }
+}
+
+/// Tells if sharing the expression could save any work.
+fn is_reducible(expr: &Value) -> bool {
+    matches!(
+        expr,
+        Value::App { .. } | Value::If { .. } | Value::Global(_)
+    )
+}
+
+impl Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Strategy::NormalOrder => "normal",
+            Strategy::ApplicativeOrder => "applicative",
+            Strategy::CallByName => "name",
+            Strategy::CallByValue => "value",
+            Strategy::CallByNeed => "need",
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Strategy::NormalOrder),
+            "applicative" => Ok(Strategy::ApplicativeOrder),
+            "name" => Ok(Strategy::CallByName),
+            "value" => Ok(Strategy::CallByValue),
+            "need" => Ok(Strategy::CallByNeed),
+            _ => eyre::bail!(
+                "unknown strategy '{}', expected normal, applicative, name, value or need",
+                s
+            ),
+        }
+    }
+}
+
+impl Display for Form {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Form::Normal => "nf",
+            Form::HeadNormal => "hnf",
+            Form::WeakHeadNormal => "whnf",
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for Form {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nf" => Ok(Form::Normal),
+            "hnf" => Ok(Form::HeadNormal),
+            "whnf" => Ok(Form::WeakHeadNormal),
+            _ => eyre::bail!("unknown form '{}', expected nf, hnf or whnf", s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::Config, derivation, step::Outcome};
+
+    use super::*;
+
+    /// `k a Ω`: only a strategy that doesn't evaluate arguments first gets rid of `Ω`.
+    const DISCARDS_OMEGA: &str = "
+        k = \\x y. x;
+        omega = (\\x. x x) (\\x. x x);
+        k a omega
+    ";
+
+    fn run(strategy: Strategy, code: &str) -> (String, Outcome) {
+        let config = Config::default()
+            .strategy(strategy)
+            .max_steps(100)
+            .detect_cycles(true);
+
+        let derivation = derivation(&config, nebula_core::parse(code).unwrap()).unwrap();
+
+        (derivation.result.to_string(), derivation.outcome)
+    }
+
+    #[test]
+    fn lazy_strategies_terminate() {
+        for strategy in [
+            Strategy::NormalOrder,
+            Strategy::CallByName,
+            Strategy::CallByNeed,
+        ] {
+            assert_eq!(
+                run(strategy, DISCARDS_OMEGA),
+                ("a".to_string(), Outcome::Done),
+                "{}",
+                strategy
+            );
+        }
+    }
+
+    #[test]
+    fn strict_strategies_diverge() {
+        for strategy in [Strategy::ApplicativeOrder, Strategy::CallByValue] {
+            let (_, outcome) = run(strategy, DISCARDS_OMEGA);
+
+            assert!(matches!(outcome, Outcome::Cycle(_)), "{}", strategy);
+        }
+    }
+
+    #[test]
+    fn call_by_need_shares_arguments() {
+        let code = "(\\x. x + x) (1 + 2)";
+        let steps = |strategy| {
+            let config = Config::default().strategy(strategy);
+            derivation(&config, nebula_core::parse(code).unwrap())
+                .unwrap()
+                .steps
+                .len()
+        };
+
+        assert!(steps(Strategy::CallByNeed) < steps(Strategy::CallByName));
+    }
+
+    #[test]
+    fn weak_head_normal_form_stops_at_lambdas() {
+        let config = Config::default().form(Form::WeakHeadNormal);
+        let prog = nebula_core::parse("\\x. (\\y. y) x").unwrap();
+
+        assert_eq!(
+            derivation(&config, prog).unwrap().result.to_string(),
+            "\\x. ((\\y. y) x)"
+        );
+    }
+
+    #[test]
+    fn parses_its_own_names() {
+        for strategy in [
+            Strategy::NormalOrder,
+            Strategy::ApplicativeOrder,
+            Strategy::CallByName,
+            Strategy::CallByValue,
+            Strategy::CallByNeed,
+        ] {
+            assert_eq!(strategy.to_string().parse::<Strategy>().unwrap(), strategy);
+        }
+
+        assert!("lazy".parse::<Strategy>().is_err());
+    }
+
+    #[test]
+    fn only_the_selected_branch_is_reduced() {
+        let code = "omega = (\\x. x x) (\\x. x x); if 1 < 2 then 10 else omega";
+
+        assert_eq!(
+            run(Strategy::NormalOrder, code),
+            ("10".to_string(), Outcome::Done)
+        );
+    }
+
+    #[test]
+    fn conditions_stuck_on_a_variable_are_left_alone() {
+        let code = "\\x. if x + 1 == 2 then 1 else (\\y. y) 2";
+
+        assert_eq!(
+            run(Strategy::NormalOrder, code),
+            (
+                "\\x. if (== (+ x 1) 2) then 1 else ((\\y. y) 2)".to_string(),
+                Outcome::Done
+            )
+        );
+    }
+
+    #[test]
+    fn conditions_must_be_booleans() {
+        for code in [
+            "if 1 then 2 else 3",
+            "if (\\x. x) then 2 else 3",
+            "if + then 2 else 3",
+        ] {
+            let (_, outcome) = run(Strategy::NormalOrder, code);
+
+            assert!(
+                matches!(&outcome, Outcome::Error(e) if e.starts_with("1:1 'if' expects a boolean")),
+                "{}: {}",
+                code,
+                outcome
+            );
+        }
+    }
+}
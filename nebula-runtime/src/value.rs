@@ -1,16 +1,25 @@
-use std::fmt::Display;
+use std::{cell::RefCell, fmt::Display, rc::Rc};
 
-use nebula_core::scope::Scope;
+use nebula_core::{lexer::is_operator, scope::Scope, Loc};
+use num_bigint::{BigInt, Sign};
+
+use crate::{prim::Prim, step::Direction};
 
 #[derive(Clone)]
 pub enum Value {
     Uninitialized,
     Var(Scope, String),
+    /// Reference to a top-level definition.
+    Global(String),
     Integer(i64),
+    /// Integer too big for an `i64`, only produced when overflowing integers are promoted.
+    BigInt(BigInt),
     Double(f64),
     Bool(bool),
     String(String),
     Char(char),
+    /// Built-in operator, with the location it was referenced at.
+    Prim(Prim, Loc),
     Fun {
         scope: Scope,
         name: String,
@@ -20,41 +29,277 @@ pub enum Value {
         lhs: Box<Value>,
         rhs: Box<Value>,
     },
+    /// Conditional, only the branch selected by the condition is ever reduced.
+    If {
+        cond: Box<Value>,
+        then: Box<Value>,
+        otherwise: Box<Value>,
+        /// Location of the `if` keyword.
+        loc: Loc,
+    },
+    /// A term shared between all the places it was substituted in. Used by call-by-need
+    /// evaluation so an argument is reduced at most once.
+    Thunk(Rc<RefCell<Value>>),
 }
 
 impl Value {
-    pub fn is_func_application(&self) -> bool {
-        if let Value::App { .. } = self {
-            return true;
+    pub fn thunk(value: Value) -> Self {
+        Value::Thunk(Rc::new(RefCell::new(value)))
+    }
+
+    /// Deep copy of the value where shared terms are replaced by their current content.
+    pub fn unshared(&self) -> Value {
+        match self {
+            Value::Fun { scope, name, body } => Value::Fun {
+                scope: scope.clone(),
+                name: name.clone(),
+                body: Box::new(body.unshared()),
+            },
+
+            Value::App { lhs, rhs } => Value::App {
+                lhs: Box::new(lhs.unshared()),
+                rhs: Box::new(rhs.unshared()),
+            },
+
+            Value::If {
+                cond,
+                then,
+                otherwise,
+                loc,
+            } => Value::If {
+                cond: Box::new(cond.unshared()),
+                then: Box::new(then.unshared()),
+                otherwise: Box::new(otherwise.unshared()),
+                loc: *loc,
+            },
+
+            Value::Thunk(thunk) => thunk.borrow().unshared(),
+
+            x => x.clone(),
+        }
+    }
+
+    /// Sub-term found by following the path. Shared terms can't be traversed.
+    pub fn at(&self, path: &[Direction]) -> Option<&Value> {
+        let mut current = self;
+
+        for direction in path {
+            current = match (current, direction) {
+                (Value::Fun { body, .. }, Direction::Body) => body,
+                (Value::App { lhs, .. }, Direction::Lhs) => lhs,
+                (Value::App { rhs, .. }, Direction::Rhs) => rhs,
+                (Value::If { cond, .. }, Direction::Cond) => cond,
+                _ => return None,
+            };
         }
 
-        false
+        Some(current)
+    }
+
+    /// Tells if the term extends as far right as possible when printed, like a lambda or a
+    /// conditional.
+    fn is_open(&self) -> bool {
+        match self {
+            Value::Fun { .. } | Value::If { .. } => true,
+            Value::Thunk(thunk) => thunk.borrow().is_open(),
+            _ => false,
+        }
+    }
+
+    /// Tells if the term is printed as an operator, which would be read as infix.
+    fn is_operator(&self) -> bool {
+        match self {
+            Value::Prim(p, _) => is_operator(&p.to_string()),
+            Value::Var(_, name) | Value::Global(name) => is_operator(name),
+            Value::Thunk(thunk) => thunk.borrow().is_operator(),
+            _ => false,
+        }
+    }
+
+    /// Tells if the term is a negative number, whose sign would be read as subtraction after
+    /// another term.
+    fn is_negative(&self) -> bool {
+        match self {
+            Value::Integer(i) => *i < 0,
+            Value::BigInt(b) => b.sign() == Sign::Minus,
+            Value::Double(d) => d.is_sign_negative(),
+            Value::Thunk(thunk) => thunk.borrow().is_negative(),
+            _ => false,
+        }
+    }
+
+    /// Number of nodes in the term.
+    pub fn size(&self) -> usize {
+        match self {
+            Value::Fun { body, .. } => 1 + body.size(),
+            Value::App { lhs, rhs } => 1 + lhs.size() + rhs.size(),
+            Value::If {
+                cond,
+                then,
+                otherwise,
+                ..
+            } => 1 + cond.size() + then.size() + otherwise.size(),
+            Value::Thunk(thunk) => thunk.borrow().size(),
+            _ => 1,
+        }
+    }
+
+    /// Tells if both terms are equal up to the renaming of bound variables.
+    pub fn alpha_eq(&self, other: &Value) -> bool {
+        self.alpha_key() == other.alpha_key()
+    }
+
+    /// Rendering of the term where bound variables are replaced by their de Bruijn index, so
+    /// alpha-equivalent terms share the same key.
+    pub fn alpha_key(&self) -> String {
+        let mut key = String::new();
+        self.write_alpha_key(&mut Vec::new(), &mut key);
+
+        key
+    }
+
+    fn write_alpha_key(&self, bound: &mut Vec<String>, key: &mut String) {
+        match self {
+            Value::Var(_, name) => match bound.iter().rev().position(|b| b == name) {
+                Some(index) => key.push_str(&format!("#{}", index)),
+                None => key.push_str(name),
+            },
+
+            Value::Fun { name, body, .. } => {
+                key.push_str("\\.");
+                bound.push(name.clone());
+                body.write_alpha_key(bound, key);
+                bound.pop();
+            }
+
+            Value::App { lhs, rhs } => {
+                key.push('(');
+                lhs.write_alpha_key(bound, key);
+                key.push(' ');
+                rhs.write_alpha_key(bound, key);
+                key.push(')');
+            }
+
+            Value::If {
+                cond,
+                then,
+                otherwise,
+                ..
+            } => {
+                key.push_str("if ");
+                cond.write_alpha_key(bound, key);
+                key.push_str(" then ");
+                then.write_alpha_key(bound, key);
+                key.push_str(" else ");
+                otherwise.write_alpha_key(bound, key);
+            }
+
+            Value::Thunk(thunk) => thunk.borrow().write_alpha_key(bound, key),
+
+            x => key.push_str(&x.to_string()),
+        }
     }
 }
 
+/// The alternate form, `{:#}`, prints lambdas with `λ` instead of `\`.
 impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::Uninitialized => write!(f, "<uninitialized>"),
             Value::Var(_, v) => v.fmt(f),
+            Value::Global(n) => n.fmt(f),
             Value::Integer(i) => i.fmt(f),
-            Value::Double(d) => d.fmt(f),
+            Value::BigInt(b) => b.fmt(f),
+            // Keeps the fraction or exponent, so a double never reads back as an integer.
+            Value::Double(d) => write!(f, "{:?}", d),
             Value::Bool(b) => b.fmt(f),
-            Value::String(s) => write!(f, "\"{}\"", s),
-            Value::Char(c) => write!(f, "'{}'", c),
+            Value::String(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    write_char(f, c, '"')?;
+                }
+                write!(f, "\"")
+            }
+            Value::Char(c) => {
+                write!(f, "'")?;
+                write_char(f, *c, '\'')?;
+                write!(f, "'")
+            }
+            Value::Prim(p, _) => p.fmt(f),
 
             Value::Fun { name, body, .. } => {
-                write!(f, "\\{}. ", name)?;
+                if f.alternate() {
+                    write!(f, "λ{}. ", name)?;
+                } else {
+                    write!(f, "\\{}. ", name)?;
+                }
+
                 body.fmt(f)
             }
 
+            // `((f a) b)` is printed `(f a b)`.
             Value::App { lhs, rhs } => {
+                let mut args = vec![rhs];
+                let mut fun = lhs;
+                while let Value::App { lhs, rhs } = fun.as_ref() {
+                    args.push(rhs);
+                    fun = lhs;
+                }
+
                 write!(f, "(")?;
-                lhs.fmt(f)?;
-                write!(f, " ")?;
-                rhs.fmt(f)?;
+                write_term(f, fun, fun.is_open())?;
+                for (i, arg) in args.iter().rev().enumerate() {
+                    write!(f, " ")?;
+                    write_term(
+                        f,
+                        arg,
+                        arg.is_operator()
+                            || arg.is_negative()
+                            || (arg.is_open() && i + 1 < args.len()),
+                    )?;
+                }
                 write!(f, ")")
             }
+
+            Value::If {
+                cond,
+                then,
+                otherwise,
+                ..
+            } => {
+                write!(f, "if ")?;
+                cond.fmt(f)?;
+                write!(f, " then ")?;
+                then.fmt(f)?;
+                write!(f, " else ")?;
+                otherwise.fmt(f)
+            }
+
+            Value::Thunk(thunk) => thunk.borrow().fmt(f),
         }
     }
 }
+
+/// Writes a term of an application, between parentheses if `paren` is set.
+fn write_term(f: &mut std::fmt::Formatter<'_>, term: &Value, paren: bool) -> std::fmt::Result {
+    if paren {
+        write!(f, "(")?;
+        term.fmt(f)?;
+        write!(f, ")")
+    } else {
+        term.fmt(f)
+    }
+}
+
+/// Writes a char of a literal delimited by `quote`, escaped so it can be read back.
+fn write_char(f: &mut std::fmt::Formatter<'_>, c: char, quote: char) -> std::fmt::Result {
+    match c {
+        '\n' => write!(f, "\\n"),
+        '\t' => write!(f, "\\t"),
+        '\r' => write!(f, "\\r"),
+        '\0' => write!(f, "\\0"),
+        '\\' => write!(f, "\\\\"),
+        _ if c == quote => write!(f, "\\{}", c),
+        _ => write!(f, "{}", c),
+    }
+}
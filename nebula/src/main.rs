@@ -1,3 +1,21 @@
-fn main() {
-    println!("Hello, world!");
+use nebula_runtime::{config::Config, derivation, step::Outcome};
+
+fn main() -> eyre::Result<()> {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => eyre::bail!("usage: nebula <file>"),
+    };
+
+    let code = std::fs::read_to_string(path)?;
+    let prog = nebula_core::parse(code)?;
+    let config = Config::default().max_steps(100_000).detect_cycles(true);
+    let derivation = derivation(&config, prog)?;
+
+    println!("{}", derivation.result);
+
+    if derivation.outcome != Outcome::Done {
+        println!("{}", derivation.outcome);
+    }
+
+    Ok(())
 }